@@ -0,0 +1,302 @@
+// scale encoded types of the erc20 module, shared by the runtime and off-chain clients
+// the runtime re-exports these types as `erc20::*`, so a client depending on this crate decodes
+// storage items, call arguments and runtime api results with exactly the definitions the chain uses
+// can be compiled with `#[no_std]`
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), feature(alloc))]
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate parity_codec_derive;
+
+use rstd::prelude::*;
+use primitives::H256;
+
+// struct to store a governed migration from an old token to a new token
+// holders receive numerator / denominator new tokens for every old token until the deadline
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct SwapProgram<U, B> {
+    pub new_token: u32,
+    pub numerator: U,
+    pub denominator: U,
+    pub deadline: B,
+}
+
+// who can receive a token
+// open tokens can be sent to anyone, whitelisted tokens only to whitelisted accounts
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransferMode {
+    Open,
+    Whitelisted,
+}
+
+impl Default for TransferMode {
+    fn default() -> Self {
+        TransferMode::Open
+    }
+}
+
+// how fee and tax charges of a token are rounded
+// down favours the payer, up favours the receiver of the charge,
+// half even rounds to the nearest unit and exact halves to the even unit (banker's rounding)
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    Down,
+    Up,
+    HalfEven,
+}
+
+// struct to store the token details needed on every transfer, mint and burn
+// kept small so that hot paths read and prove as little as possible
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Erc20Token<U> {
+    pub total_supply: U,
+    pub transfer_mode: TransferMode,
+    // cap on the total supply, set at creation and never changed
+    pub max_supply: Option<U>,
+    // whether new tokens can be minted after creation
+    pub mintable: bool,
+    // whether holders can burn their tokens
+    pub burnable: bool,
+}
+
+// struct to store the descriptive token details, rarely read on chain
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct TokenMetadata {
+    pub name: Vec<u8>,
+    pub ticker: Vec<u8>,
+    // link to off-chain token info, e.g. an ipfs cid or an https url
+    pub uri: Option<Vec<u8>>,
+    // hash of the off-chain token info committed by the owner, so that clients can verify it
+    pub metadata_hash: Option<H256>,
+    // number of decimal places of a whole token, balances are counted in the smallest unit
+    // sale prices and bonding curve prices are quoted per whole token
+    pub decimals: u8,
+}
+
+// token details and metadata together, as returned to front-ends
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct TokenInfo<U> {
+    pub details: Erc20Token<U>,
+    pub metadata: TokenMetadata,
+}
+
+// struct to store a pending mint offer
+// the tokens are only minted once the recipient accepts the offer, before it expires
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct MintOffer<A, U, B> {
+    pub token_id: u32,
+    pub to: A,
+    pub amount: U,
+    pub expires_at: B,
+}
+
+// struct to store the guardians an account has registered for recovery
+// threshold is the number of guardian approvals needed
+// delay is the number of blocks between the start of a recovery and its completion
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct GuardianSet<A, B> {
+    pub guardians: Vec<A>,
+    pub threshold: u32,
+    pub delay: B,
+}
+
+// struct to store an active recovery of a lost account
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Recovery<A, B> {
+    pub new_account: A,
+    pub started: B,
+    pub approvals: Vec<A>,
+}
+
+// struct to store a sub-allowance delegated by a spender out of its own allowance
+// epoch is the allowance epoch of the root approval when the sub-allowance was made
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct SubAllowance<U> {
+    pub value: U,
+    pub epoch: u32,
+}
+
+// kinds of runtime logic modules a token can be bound to
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogicKind {
+    // charges a fee on transfers
+    Fee,
+    // accepts or rejects transfers
+    Restriction,
+    // reacts to transfers, e.g. to pay out rewards
+    Reward,
+}
+
+// address of an ethereum account
+pub type EthereumAddress = [u8; 20];
+
+// recoverable ecdsa signature made with an ethereum key, as r, s and v
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct EcdsaSignature(pub [u8; 32], pub [u8; 32], pub u8);
+
+// struct to store a credit line granted to a borrower
+// the grantor covers transfers of the borrower beyond its balance up to the limit
+// debt is what the borrower owes the grantor, repaid out of what the borrower receives
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct CreditLine<A, U> {
+    pub grantor: A,
+    pub limit: U,
+    pub debt: U,
+}
+
+// identifier of a lock on a token balance, chosen by the module that sets it
+pub type LockIdentifier = [u8; 8];
+
+// identifier of the reason for a hold on a token balance, chosen by the module that holds it
+pub type HoldReason = [u8; 8];
+
+// the reason the erc20 module holds the tokens locked in a hashed timelock under
+pub const HTLC_HOLD: HoldReason = *b"erc20htl";
+
+// the reason the erc20 module holds the tokens offered in a crowdsale under
+pub const SALE_HOLD: HoldReason = *b"erc20sal";
+
+// lp tokens minted to the pool account on the first deposit into a pool, which can never be redeemed
+// keeps the first provider from shrinking the lp supply to a dust amount and inflating the price of a share
+pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
+// max decimal places of a token, so that a whole token fits in a 64-bit balance
+pub const MAX_DECIMALS: u8 = 18;
+
+// struct to store a forced buyout of the minority holders of a token
+// holders receive numerator / denominator payout tokens for every bought out token
+// the buyout needs governance approval and can only be executed after the dissent window
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Buyout<U, B> {
+    pub payout_token: u32,
+    pub numerator: U,
+    pub denominator: U,
+    pub window_end: B,
+    pub approved: bool,
+    // counts the buyouts of the token, so that dissents of a cancelled buyout do not carry over
+    pub round: u32,
+}
+
+// struct to store an escrowed token payment
+// the amount is held on the payer's balance until it is released to the beneficiary
+// or refunded to the payer
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Escrow<A, U> {
+    pub token_id: u32,
+    pub payer: A,
+    pub beneficiary: A,
+    pub arbiter: A,
+    pub amount: U,
+}
+
+// struct to store a hashed timelock contract
+// the amount is held on the sender's balance until the preimage of the hash is revealed,
+// or until the timeout has passed and the tokens are refunded
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Htlc<A, U, B> {
+    pub token_id: u32,
+    pub from: A,
+    pub to: A,
+    pub amount: U,
+    pub timeout: B,
+}
+
+// struct to store a crowdsale of a token for the native currency
+// the cap is held on the issuer's balance for the duration of the sale
+// payments are reserved on the native balance of the sale account of the token until the sale is finalised,
+// so that they can be refunded if the soft cap is not reached
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Sale<A, U, N, B> {
+    pub issuer: A,
+    // native balance per unit of the token
+    pub price: N,
+    pub cap: U,
+    pub soft_cap: U,
+    pub start: B,
+    pub end: B,
+    pub sold: U,
+    pub raised: N,
+    // number of buyers that have not claimed their tokens or refund yet
+    pub buyers: u32,
+    pub finalised: bool,
+}
+
+// state of a fair launch, a crowdsale whose orders may exceed the cap
+// orders are laid out one after another, ordered is the number of units ordered in total
+// an oversubscribed launch draws the position of the first filled unit when it is finalised
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct FairLaunch<U> {
+    pub ordered: U,
+    pub draw: Option<U>,
+}
+
+// price curve of a token issued against the native currency
+// prices are in native balance per unit of the token, at a given curve supply
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum BondingCurve<U, N> {
+    // price = base + supply * slope numerator / slope denominator
+    // base, slope numerator, slope denominator
+    Linear(N, N, N),
+    // price = base * (1 + growth / 1_000_000) ^ (supply / step), constant within a step
+    // base, growth in parts per million, step
+    Exponential(N, u32, U),
+}
+
+// struct to store the bonding curve issuance of a token
+// supply is the amount issued through the curve, so it excludes tokens minted by the owner
+// the reserve is reserved on the native balance of the curve account of the token, derived from its id,
+// so that it cannot be spent while the issued tokens are outstanding
+// the custodian, the owner that enabled the curve, receives the rounding left when the curve is disabled
+#[derive(Encode, Decode, Clone, PartialEq, Debug)]
+pub struct CurveIssuance<A, U, N> {
+    pub curve: BondingCurve<U, N>,
+    pub custodian: A,
+    pub supply: U,
+    pub reserve: N,
+}
+
+// struct to store a community proposal to correct the name and ticker of a token
+// the bond is reserved from the proposer until the proposal is resolved
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct ModerationProposal<A, N> {
+    pub proposer: A,
+    pub name: Vec<u8>,
+    pub ticker: Vec<u8>,
+    pub bond: N,
+}
+
+// open, high, low and close price of a pool over one candle period, with the volume traded in it
+// prices are units of token b per whole token a, the volume is in units of token a
+// the period is an index, the candle starts at block period * candle period
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Candle<B> {
+    pub period: u64,
+    pub open: B,
+    pub high: B,
+    pub low: B,
+    pub close: B,
+    pub volume: B,
+}
+
+// struct to store a constant product liquidity pool between two tokens
+// the reserves are the balances of the pool account, which has no key and is only moved by the module
+// liquidity providers hold the lp token of the pool, which is owned by the pool account
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct Pool<A> {
+    pub token_a: u32,
+    pub token_b: u32,
+    pub account: A,
+    pub lp_token: u32,
+}
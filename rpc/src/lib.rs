@@ -0,0 +1,102 @@
+//! JSON-RPC methods for querying erc20 tokens, backed by the `Erc20Api` runtime api.
+//!
+//! Saves wallets from computing storage keys client side. All methods take an optional
+//! block hash and query the best block when it is omitted.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_macros::build_rpc_trait;
+use parity_codec::Codec;
+use serde::Serialize;
+use sr_primitives::generic::BlockId;
+use sr_primitives::traits::{Block as BlockT, ProvideRuntimeApi};
+use substrate_client::blockchain::HeaderBackend;
+
+use node_template_runtime::erc20::TokenInfo;
+pub use node_template_runtime::erc20::api::Erc20Api as Erc20RuntimeApi;
+
+build_rpc_trait! {
+	/// Erc20 token queries.
+	pub trait Erc20Api<AccountId, Balance, Hash> {
+		/// Balance of an account for a token.
+		#[rpc(name = "erc20_balanceOf")]
+		fn balance_of(&self, u32, AccountId, Option<Hash>) -> Result<Balance>;
+
+		/// Allowance an owner has given to a spender for a token.
+		#[rpc(name = "erc20_allowance")]
+		fn allowance(&self, u32, AccountId, AccountId, Option<Hash>) -> Result<Balance>;
+
+		/// Details of a token, `null` if the token does not exist.
+		#[rpc(name = "erc20_tokenInfo")]
+		fn token_info(&self, u32, Option<Hash>) -> Result<Option<TokenInfo<Balance>>>;
+
+		/// Ids of the existing tokens, destroyed tokens are left out.
+		#[rpc(name = "erc20_tokens")]
+		fn tokens(&self, Option<Hash>) -> Result<Vec<u32>>;
+	}
+}
+
+/// Implementation of the erc20 RPC methods over a client.
+pub struct Erc20<C, Block, AccountId, Balance> {
+	client: Arc<C>,
+	_marker: PhantomData<(Block, AccountId, Balance)>,
+}
+
+impl<C, Block, AccountId, Balance> Erc20<C, Block, AccountId, Balance> {
+	/// Create new erc20 RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Erc20 { client, _marker: PhantomData }
+	}
+}
+
+impl<C, Block, AccountId, Balance> Erc20<C, Block, AccountId, Balance> where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	fn block_id(&self, at: Option<Block::Hash>) -> Result<BlockId<Block>> {
+		match at {
+			Some(hash) => Ok(BlockId::hash(hash)),
+			None => self.client.info()
+				.map(|info| BlockId::hash(info.best_hash))
+				.map_err(runtime_error),
+		}
+	}
+}
+
+impl<C, Block, AccountId, Balance> Erc20Api<AccountId, Balance, Block::Hash> for Erc20<C, Block, AccountId, Balance> where
+	Block: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: Erc20RuntimeApi<Block, AccountId, Balance>,
+	AccountId: Codec + Send + Sync + 'static,
+	Balance: Codec + Serialize + Send + Sync + 'static,
+{
+	fn balance_of(&self, token_id: u32, who: AccountId, at: Option<Block::Hash>) -> Result<Balance> {
+		let at = self.block_id(at)?;
+		self.client.runtime_api().balance_of(&at, token_id, who).map_err(runtime_error)
+	}
+
+	fn allowance(&self, token_id: u32, owner: AccountId, spender: AccountId, at: Option<Block::Hash>) -> Result<Balance> {
+		let at = self.block_id(at)?;
+		self.client.runtime_api().allowance(&at, token_id, owner, spender).map_err(runtime_error)
+	}
+
+	fn token_info(&self, token_id: u32, at: Option<Block::Hash>) -> Result<Option<TokenInfo<Balance>>> {
+		let at = self.block_id(at)?;
+		self.client.runtime_api().token_metadata(&at, token_id).map_err(runtime_error)
+	}
+
+	fn tokens(&self, at: Option<Block::Hash>) -> Result<Vec<u32>> {
+		let at = self.block_id(at)?;
+		self.client.runtime_api().tokens(&at).map_err(runtime_error)
+	}
+}
+
+fn runtime_error<E: std::fmt::Debug>(e: E) -> Error {
+	Error {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query the runtime.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
@@ -1,22 +1,56 @@
 use rstd::prelude::*;
-use parity_codec::Codec;
+use rstd::collections::btree_map::BTreeMap;
+use parity_codec::{Codec, Encode};
 use support::{dispatch::Result, Parameter, StorageMap, StorageValue, decl_storage, decl_module, decl_event, ensure};
-use runtime_primitives::traits::{CheckedSub, CheckedAdd, Member, SimpleArithmetic, As};
-use system::{self, ensure_signed};
+use runtime_primitives::traits::{Member, SimpleArithmetic, As, Zero, MaybeSerializeDebug, EnsureOrigin, Verify, Hash, CheckedMul};
+use system::{self, ensure_signed, ensure_root};
+use balances;
+use primitives::H256;
+use self::double_map::StorageDoubleMap;
+use self::ledger::Ledger;
+#[cfg(feature = "std")]
+use runtime_io::{with_storage, blake2_256};
+
+pub mod math;
+pub mod double_map;
+pub mod api;
+mod types;
+mod impls;
+mod functions;
+mod ledger;
+#[cfg(test)]
+mod tests;
+
+pub use self::types::*;
+pub use self::impls::*;
+
+// stable import surface for downstream runtimes and modules
+// internals can move between files without breaking these paths
+pub mod prelude {
+    pub use super::{Trait, Module, Call, Event, RawEvent};
+    pub use super::types::*;
+    pub use super::impls::*;
+}
 
 // the module trait
 // contains type definitions
-pub trait Trait: system::Trait {
+pub trait Trait: balances::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type TokenBalance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy + As<usize> + As<u64>;
-}
-
-// struct to store the token details
-#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
-pub struct Erc20Token<U> {
-    name: Vec<u8>,
-    ticker: Vec<u8>,
-    total_supply: U,
+    type TokenBalance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy + As<usize> + As<u64> + MaybeSerializeDebug;
+    // handler called after every successful token transfer
+    type TransferHandler: OnTokenTransfer<Self::AccountId, Self::TokenBalance>;
+    // origin allowed to force transfers between any accounts
+    type ForceOrigin: EnsureOrigin<Self::Origin>;
+    // origin that resolves community proposals to correct the metadata of a token
+    type ModerationOrigin: EnsureOrigin<Self::Origin>;
+    // compliance (e.g. kyc) check consulted before every transfer
+    type ComplianceCheck: CanTransfer<Self::AccountId>;
+    // signature of off-chain signed messages, verified against the signing account
+    type Signature: Parameter + Verify<Signer = Self::AccountId>;
+    // logic modules provided by the runtime that tokens can bind to
+    type LogicModules: LogicModules<Self::AccountId, Self::TokenBalance>;
+    // treasury that tokens can direct their transfer fees to
+    type FeeDestination: FeeDestination<Self::AccountId>;
 }
 
 // public interface for this runtime module
@@ -25,31 +59,31 @@ decl_module! {
       // initialize the default event for this module
       fn deposit_event<T>() = default;
 
+      // commits the transfers of this block to a merkle root per token
+      // also adjusts the token creation fee to the creations of this block
+      fn on_finalise(n: T::BlockNumber) {
+          Self::commit_transfer_roots(n);
+          Self::commit_quiet_transfers();
+          Self::update_creation_fee();
+      }
+
       // initializes a new token
       // generates an integer token_id so that all tokens are unique
       // takes a name, ticker, total supply for the token
+      // takes an optional immutable cap on the supply and whether the owner can mint more tokens
+      // takes whether holders can burn their tokens
       // makes the initiating account the owner of the token
       // the balance of the owner is set to total supply
-      fn init(origin, name: Vec<u8>, ticker: Vec<u8>, total_supply: T::TokenBalance) -> Result {
+      // tickers with a reserved prefix can only be created by other runtime modules
+      fn init(origin, name: Vec<u8>, ticker: Vec<u8>, total_supply: T::TokenBalance, max_supply: Option<T::TokenBalance>, mintable: bool, burnable: bool) -> Result {
           let sender = ensure_signed(origin)?;
+          Self::ensure_ticker_not_reserved(&ticker)?;
+          Self::ensure_can_init(&name, &ticker, total_supply, max_supply)?;
+          Self::charge_creation_fee(&sender, 1)?;
+          let token_id = Self::_init(sender.clone(), name.clone(), ticker.clone(), total_supply, max_supply, mintable, burnable)?;
+          Self::record_issuer_deposit(token_id, &sender);
 
-          // checking max size for name and ticker
-          // byte arrays (vecs) with no max size should be avoided
-          ensure!(name.len() <= 64, "token name cannot exceed 64 bytes");
-          ensure!(ticker.len() <= 32, "token ticker cannot exceed 32 bytes");
-
-          let token_id = Self::token_id();
-          let next_token_id = token_id.checked_add(1).ok_or("overflow in calculating next token id")?;
-          <TokenId<T>>::put(next_token_id);
-
-          let token = Erc20Token {
-              name,
-              ticker,
-              total_supply,
-          };
-
-          <Tokens<T>>::insert(token_id, token);
-          <BalanceOf<T>>::insert((token_id, sender), total_supply);
+          Self::deposit_event(RawEvent::TokenCreated(token_id, sender, name, ticker, total_supply));
 
           Ok(())
       }
@@ -65,89 +99,2831 @@ decl_module! {
       // once this is done, transfer_from can be called with corresponding values
       fn approve(_origin, token_id: u32, spender: T::AccountId, value: T::TokenBalance) -> Result {
           let sender = ensure_signed(_origin)?;
-          ensure!(<BalanceOf<T>>::exists((token_id, sender.clone())), "Account does not own this token");
+          Self::ensure_can_approve(token_id, &sender, &spender)?;
+          ensure!(Self::has_balance(token_id, &sender), "Account does not own this token");
 
-          let allowance = Self::allowance((token_id, sender.clone(), spender.clone()));
-          let updated_allowance = allowance.checked_add(&value).ok_or("overflow in calculating allowance")?;
-          <Allowance<T>>::insert((token_id, sender.clone(), spender.clone()), updated_allowance);
+          let allowance = Self::allowance(token_id, &sender, &spender);
+          let updated_allowance = math::checked_add(allowance, value, "overflow in calculating allowance")?;
+          Self::write_allowance(token_id, &sender, &spender, updated_allowance);
+          <AllowanceExpiry<T>>::remove((token_id, sender.clone(), spender.clone()));
 
           Self::deposit_event(RawEvent::Approval(token_id, sender.clone(), spender.clone(), value));
 
           Ok(())
       }
 
+      // sets the allowance of a spender to an exact value
+      // to avoid the approve race condition, the allowance must be reset to zero
+      // before it can be changed to another non-zero value
+      fn set_allowance(origin, token_id: u32, spender: T::AccountId, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_can_approve(token_id, &sender, &spender)?;
+          ensure!(Self::has_balance(token_id, &sender), "Account does not own this token");
+
+          let allowance = Self::allowance(token_id, &sender, &spender);
+          ensure!(allowance.is_zero() || value.is_zero(), "Allowance must be reset to zero before changing it");
+          Self::write_allowance(token_id, &sender, &spender, value);
+          <AllowanceExpiry<T>>::remove((token_id, sender.clone(), spender.clone()));
+
+          Self::deposit_event(RawEvent::Approval(token_id, sender, spender, value));
+
+          Ok(())
+      }
+
       // the ERC20 standard transfer_from function
       // implemented in the open-zeppelin way - increase/decrease allownace
-      // if approved, transfer from an account to another account without owner's signature
-      pub fn transfer_from(_origin, token_id: u32, from: T::AccountId, to: T::AccountId, value: T::TokenBalance) -> Result {
-        ensure!(<Allowance<T>>::exists((token_id, from.clone(), to.clone())), "Allowance does not exist.");
-        let allowance = Self::allowance((token_id, from.clone(), to.clone()));
-        ensure!(allowance >= value, "Not enough allowance.");
-          
-        // using checked_sub (safe math) to avoid overflow
-        let updated_allowance = allowance.checked_sub(&value).ok_or("overflow in calculating allowance")?;
-        <Allowance<T>>::insert((token_id, from.clone(), to.clone()), updated_allowance);
-
-        Self::deposit_event(RawEvent::Approval(token_id, from.clone(), to.clone(), value));
-        Self::_transfer(token_id, from, to, value)
+      // if approved, the caller transfers from an account to another account without owner's signature
+      // the allowance spent is the one the `from` account has given to the caller
+      pub fn transfer_from(origin, token_id: u32, from: T::AccountId, to: T::AccountId, value: T::TokenBalance) -> Result {
+          let spender = ensure_signed(origin)?;
+          ensure!(!Self::is_blacklisted((token_id, spender.clone())), "Spender account is blacklisted");
+          ensure!(Self::has_allowance(token_id, &from, &spender), "Allowance does not exist.");
+          Self::ensure_allowance_active(token_id, &from, &spender)?;
+          let allowance = Self::allowance(token_id, &from, &spender);
+          ensure!(allowance >= value, "Not enough allowance.");
+
+          // using checked math to avoid overflow
+          let updated_allowance = math::checked_sub(allowance, value, "overflow in calculating allowance")?;
+          // the transfer is checked before the allowance is spent
+          let mut ledger = Ledger::new(token_id);
+          ledger.transfer(&from, &to, value, None)?;
+
+          Self::write_allowance(token_id, &from, &spender, updated_allowance);
+          Self::deposit_event(RawEvent::Approval(token_id, from, spender, value));
+          ledger.commit();
+
+          Ok(())
       }
-  }
-}
 
-// storage for this module
-decl_storage! {
-  trait Store for Module<T: Trait> as Erc20 {
-      // token id nonce for storing the next token id available for token initialization
-      // inspired by the AssetId in the SRML assets module
-      TokenId get(token_id): u32;
-      // details of the token corresponding to a token id
-      Tokens get(token_details): map u32 => Erc20Token<T::TokenBalance>;
-      // balances mapping for an account and token
-      BalanceOf get(balance_of): map (u32, T::AccountId) => T::TokenBalance;
-      // allowance for an account and token
-      Allowance get(allowance): map (u32, T::AccountId, T::AccountId) => T::TokenBalance;
-  }
-}
+      // executes many transfer_from legs in one call
+      // each leg debits the allowance the `from` account has given to the caller
+      // all legs are checked before anything is written, so either all succeed or none do
+      fn transfer_from_many(origin, token_id: u32, transfers: Vec<(T::AccountId, T::AccountId, T::TokenBalance)>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(transfers.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+          ensure!(!Self::is_blacklisted((token_id, sender.clone())), "Spender account is blacklisted");
 
-// events
-decl_event!(
-    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Balance = <T as self::Trait>::TokenBalance {
-        // event for transfer of tokens
-        // tokenid, from, to, value
-        Transfer(u32, AccountId, AccountId, Balance),
-        // event when an approval is made
-        // tokenid, owner, spender, value
-        Approval(u32, AccountId, AccountId, Balance),
-    }
-);
+          // simulate all the allowance spends on in-memory copies of the touched allowances
+          let mut allowances = BTreeMap::new();
+          for (from, _, value) in transfers.iter() {
+              ensure!(Self::has_allowance(token_id, from, &sender), "Allowance does not exist.");
+              Self::ensure_allowance_active(token_id, from, &sender)?;
+              let allowance = allowances.entry(from.clone())
+                  .or_insert_with(|| Self::allowance(token_id, from, &sender));
+              ensure!(*allowance >= *value, "Not enough allowance.");
+              *allowance = math::checked_sub(*allowance, *value, "overflow in calculating allowance")?;
+          }
+          let ledger = Self::plan_transfers(token_id, &transfers)?;
 
-// implementation of mudule
-// utility and private functions
-// if marked public, accessible by other modules
-impl<T: Trait> Module<T> {
-    // the ERC20 standard transfer function
-    // internal
-    fn _transfer(
-        token_id: u32,
-        from: T::AccountId,
-        to: T::AccountId,
-        value: T::TokenBalance,
-    ) -> Result {
-        ensure!(<BalanceOf<T>>::exists((token_id, from.clone())), "Account does not own this token");
-        let sender_balance = Self::balance_of((token_id, from.clone()));
-        ensure!(sender_balance >= value, "Not enough balance.");
-
-        let updated_from_balance = sender_balance.checked_sub(&value).ok_or("overflow in calculating balance")?;
-        let receiver_balance = Self::balance_of((token_id, to.clone()));
-        let updated_to_balance = receiver_balance.checked_add(&value).ok_or("overflow in calculating balance")?;
-        
-        // reduce sender's balance
-        <BalanceOf<T>>::insert((token_id, from.clone()), updated_from_balance);
-
-        // increase receiver's balance
-        <BalanceOf<T>>::insert((token_id, to.clone()), updated_to_balance);
-
-        Self::deposit_event(RawEvent::Transfer(token_id, from, to, value));
-        Ok(())
-    }
-}
+          for (from, value) in allowances {
+              Self::write_allowance(token_id, &from, &sender, value);
+          }
+          for (from, _, value) in transfers.iter() {
+              Self::deposit_event(RawEvent::Approval(token_id, from.clone(), sender.clone(), *value));
+          }
+          ledger.commit();
+          Self::store_receipt(&(token_id, transfers));
+
+          Ok(())
+      }
+
+      // offers to mint new tokens to an account
+      // only the owner of the token can make an offer
+      // nothing is minted until the recipient accepts the offer, which it can until the expiry block
+      fn offer_mint(origin, token_id: u32, to: T::AccountId, amount: T::TokenBalance, expires_at: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+          ensure!(Self::token_details(token_id).mintable, "Token is not mintable");
+          ensure!(expires_at > <system::Module<T>>::block_number(), "Mint offer must expire in the future");
+
+          let offer_id = Self::mint_offer_id();
+          let next_offer_id = offer_id.checked_add(1).ok_or("overflow in calculating next mint offer id")?;
+          <MintOfferId<T>>::put(next_offer_id);
+
+          let offer = MintOffer {
+              token_id,
+              to: to.clone(),
+              amount,
+              expires_at,
+          };
+          <MintOffers<T>>::insert(offer_id, offer);
+          <MintOfferCount<T>>::mutate(token_id, |count| *count = count.saturating_add(1));
+
+          Self::deposit_event(RawEvent::MintOffered(offer_id, token_id, to, amount));
+
+          Ok(())
+      }
+
+      // accepts a pending mint offer
+      // only the recipient of the offer can accept it
+      // mints the offered amount to the recipient and removes the offer
+      fn accept_mint(origin, offer_id: u64) -> Result {
+          let sender = ensure_signed(origin)?;
+          let offer = Self::mint_offer(offer_id).ok_or("Mint offer does not exist")?;
+          ensure!(offer.to == sender, "Mint offer is not for this account");
+          ensure!(<system::Module<T>>::block_number() <= offer.expires_at, "Mint offer has expired");
+
+          Self::_mint(offer.token_id, offer.to, offer.amount)?;
+          Self::remove_mint_offer(offer_id, offer.token_id);
+
+          Ok(())
+      }
+
+      // removes a pending mint offer without minting
+      // the owner of the token can withdraw it and the recipient can reject it
+      // anyone can remove it once it has expired
+      fn cancel_mint_offer(origin, offer_id: u64) -> Result {
+          let sender = ensure_signed(origin)?;
+          let offer = Self::mint_offer(offer_id).ok_or("Mint offer does not exist")?;
+          let expired = <system::Module<T>>::block_number() > offer.expires_at;
+          ensure!(expired || sender == offer.to || Self::owner_of(offer.token_id) == Some(sender), "Not allowed to cancel this mint offer");
+
+          Self::remove_mint_offer(offer_id, offer.token_id);
+
+          Self::deposit_event(RawEvent::MintOfferCancelled(offer_id));
+
+          Ok(())
+      }
+
+      // registers the guardians that can recover the balances of the sender
+      // replaces any previously registered guardians
+      fn set_guardians(origin, guardians: Vec<T::AccountId>, threshold: u32, delay: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+
+          // bounded so that approving a recovery stays cheap
+          ensure!(guardians.len() <= Self::max_guardians() as usize, "cannot register more than the max guardians");
+          ensure!(threshold > 0 && threshold as usize <= guardians.len(), "invalid guardian threshold");
+          ensure!(!<Recoveries<T>>::exists(sender.clone()), "Recovery in progress for this account");
+
+          let guardian_set = GuardianSet {
+              guardians,
+              threshold,
+              delay,
+          };
+          <Guardians<T>>::insert(sender.clone(), guardian_set);
+
+          Self::deposit_event(RawEvent::GuardiansSet(sender, threshold));
+
+          Ok(())
+      }
+
+      // removes the guardians of the sender, disabling recovery
+      fn remove_guardians(origin) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Guardians<T>>::exists(sender.clone()), "Account has no guardians");
+          ensure!(!<Recoveries<T>>::exists(sender.clone()), "Recovery in progress for this account");
+
+          <Guardians<T>>::remove(sender.clone());
+
+          Self::deposit_event(RawEvent::GuardiansRemoved(sender));
+
+          Ok(())
+      }
+
+      // called by a guardian to approve the recovery of a lost account to a new account
+      // the first approval starts the recovery delay
+      fn approve_recovery(origin, lost: T::AccountId, new_account: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          let guardian_set = Self::guardians(lost.clone()).ok_or("Account has no guardians")?;
+          ensure!(guardian_set.guardians.contains(&sender), "Only a guardian of the account can do this");
+
+          let mut recovery = match Self::recovery(lost.clone()) {
+              Some(recovery) => recovery,
+              None => {
+                  Self::deposit_event(RawEvent::RecoveryStarted(lost.clone(), new_account.clone()));
+                  Recovery {
+                      new_account: new_account.clone(),
+                      started: <system::Module<T>>::block_number(),
+                      approvals: Vec::new(),
+                  }
+              }
+          };
+          ensure!(recovery.new_account == new_account, "Recovery in progress to another account");
+          ensure!(!recovery.approvals.contains(&sender), "Guardian has already approved this recovery");
+
+          recovery.approvals.push(sender.clone());
+          <Recoveries<T>>::insert(lost.clone(), recovery);
+
+          Self::deposit_event(RawEvent::RecoveryApproved(lost, sender));
+
+          Ok(())
+      }
+
+      // cancels an active recovery of the sender
+      // lets the original key veto a malicious recovery during the delay
+      fn cancel_recovery(origin) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Recoveries<T>>::exists(sender.clone()), "No recovery in progress for this account");
+
+          <Recoveries<T>>::remove(sender.clone());
+
+          Self::deposit_event(RawEvent::RecoveryCancelled(sender));
+
+          Ok(())
+      }
+
+      // completes the recovery of a lost account once enough guardians approved and the delay passed
+      // moves all token balances and token ownerships of the lost account to the new account
+      fn complete_recovery(origin, lost: T::AccountId) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let guardian_set = Self::guardians(lost.clone()).ok_or("Account has no guardians")?;
+          let recovery = Self::recovery(lost.clone()).ok_or("No recovery in progress for this account")?;
+
+          ensure!(recovery.approvals.len() >= guardian_set.threshold as usize, "Not enough guardian approvals");
+          let now = <system::Module<T>>::block_number();
+          ensure!(now >= recovery.started + guardian_set.delay, "Recovery delay has not passed yet");
+
+          Self::_recover(lost.clone(), recovery.new_account.clone())?;
+
+          <Recoveries<T>>::remove(lost.clone());
+          <Guardians<T>>::remove(lost.clone());
+
+          Self::deposit_event(RawEvent::RecoveryCompleted(lost, recovery.new_account));
+
+          Ok(())
+      }
+
+      // pauses all transfers of a token
+      // only the owner of the token can pause it
+      fn pause(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_paused(token_id), "Token is already paused");
+
+          <Paused<T>>::insert(token_id, true);
+
+          Self::deposit_event(RawEvent::Paused(token_id));
+
+          Ok(())
+      }
+
+      // resumes the transfers of a paused token
+      // only the owner of the token can unpause it
+      fn unpause(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_paused(token_id), "Token is not paused");
+
+          <Paused<T>>::remove(token_id);
+
+          Self::deposit_event(RawEvent::Unpaused(token_id));
+
+          Ok(())
+      }
+
+      // adds a token to the chain-wide denylist, blocking all its transfers and approvals
+      // can only be called by the root origin (governance)
+      fn deny_token(origin, token_id: u32) -> Result {
+          ensure_root(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+          ensure!(!Self::is_denied(token_id), "Token is already denied");
+
+          <DeniedTokens<T>>::insert(token_id, true);
+
+          Self::deposit_event(RawEvent::TokenDenied(token_id));
+
+          Ok(())
+      }
+
+      // removes a token from the chain-wide denylist
+      // can only be called by the root origin (governance)
+      fn allow_token(origin, token_id: u32) -> Result {
+          ensure_root(origin)?;
+          ensure!(Self::is_denied(token_id), "Token is not denied");
+
+          <DeniedTokens<T>>::remove(token_id);
+
+          Self::deposit_event(RawEvent::TokenAllowed(token_id));
+
+          Ok(())
+      }
+
+      // freezes an account for a token, blocking all transfers from and to it
+      // only the owner of the token can freeze accounts
+      fn freeze_account(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_frozen((token_id, who.clone())), "Account is already frozen");
+
+          <Frozen<T>>::insert((token_id, who.clone()), true);
+
+          Self::deposit_event(RawEvent::AccountFrozen(token_id, who));
+
+          Ok(())
+      }
+
+      // thaws a frozen account for a token
+      // only the owner of the token can thaw accounts
+      fn thaw_account(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_frozen((token_id, who.clone())), "Account is not frozen");
+
+          <Frozen<T>>::remove((token_id, who.clone()));
+
+          Self::deposit_event(RawEvent::AccountThawed(token_id, who));
+
+          Ok(())
+      }
+
+      // maps an asset id of another asset registry (e.g. pallet-assets, orml tokens) to a token id
+      // the external id is the SCALE encoded asset id of that registry
+      // can only be called by the root origin (governance)
+      fn map_external_asset(origin, registry: u8, external_id: Vec<u8>, token_id: u32) -> Result {
+          ensure_root(origin)?;
+          ensure!(external_id.len() <= Self::max_external_id_length() as usize, "external asset id is too long");
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+          ensure!(!<TokenOfExternalAsset<T>>::exists((registry, external_id.clone())), "External asset is already mapped");
+          ensure!(!<ExternalAssetOfToken<T>>::exists((token_id, registry)), "Token is already mapped in this registry");
+
+          <TokenOfExternalAsset<T>>::insert((registry, external_id.clone()), token_id);
+          <ExternalAssetOfToken<T>>::insert((token_id, registry), external_id.clone());
+
+          Self::deposit_event(RawEvent::ExternalAssetMapped(registry, external_id, token_id));
+
+          Ok(())
+      }
+
+      // removes the mapping of an external asset id
+      // can only be called by the root origin (governance)
+      fn unmap_external_asset(origin, registry: u8, external_id: Vec<u8>) -> Result {
+          ensure_root(origin)?;
+          let token_id = Self::token_of_external_asset((registry, external_id.clone())).ok_or("External asset is not mapped")?;
+
+          <TokenOfExternalAsset<T>>::remove((registry, external_id.clone()));
+          <ExternalAssetOfToken<T>>::remove((token_id, registry));
+
+          Self::deposit_event(RawEvent::ExternalAssetUnmapped(registry, external_id, token_id));
+
+          Ok(())
+      }
+
+      // moves tokens between any two accounts without an allowance
+      // bypasses pauses, freezes and other transfer restrictions, but not the locks of other modules
+      // can only be called by the configured force origin (e.g. governance)
+      fn force_transfer(origin, token_id: u32, from: T::AccountId, to: T::AccountId, value: T::TokenBalance) -> Result {
+          T::ForceOrigin::ensure_origin(origin)?;
+          Self::ensure_admin_active(token_id)?;
+          Self::_move(token_id, from.clone(), to.clone(), value)?;
+
+          Self::record_transfer(token_id, &from, &to, value);
+          Self::deposit_event(RawEvent::ForcedTransfer(token_id, from, to, value));
+
+          Ok(())
+      }
+
+      // adds an account to the blacklist of a token
+      // blacklisted accounts cannot send, receive, approve or be approved
+      // only the owner of the token can maintain the blacklist
+      fn add_to_blacklist(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_blacklisted((token_id, who.clone())), "Account is already blacklisted");
+
+          <Blacklist<T>>::insert((token_id, who.clone()), true);
+
+          Self::deposit_event(RawEvent::Blacklisted(token_id, who));
+
+          Ok(())
+      }
+
+      // removes an account from the blacklist of a token
+      // only the owner of the token can maintain the blacklist
+      fn remove_from_blacklist(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_blacklisted((token_id, who.clone())), "Account is not blacklisted");
+
+          <Blacklist<T>>::remove((token_id, who.clone()));
+
+          Self::deposit_event(RawEvent::RemovedFromBlacklist(token_id, who));
+
+          Ok(())
+      }
+
+      // sets who can receive a token
+      // only the owner of the token can change the transfer mode
+      fn set_transfer_mode(origin, token_id: u32, transfer_mode: TransferMode) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          let mut token = Self::token_details(token_id);
+          token.transfer_mode = transfer_mode;
+          <Tokens<T>>::insert(token_id, token);
+
+          Self::deposit_event(RawEvent::TransferModeChanged(token_id, transfer_mode));
+
+          Ok(())
+      }
+
+      // adds an account to the whitelist of a token
+      // only the owner of the token can maintain the whitelist
+      fn add_to_whitelist(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_whitelisted((token_id, who.clone())), "Account is already whitelisted");
+
+          <Whitelist<T>>::insert((token_id, who.clone()), true);
+
+          Self::deposit_event(RawEvent::Whitelisted(token_id, who));
+
+          Ok(())
+      }
+
+      // removes an account from the whitelist of a token
+      // only the owner of the token can maintain the whitelist
+      fn remove_from_whitelist(origin, token_id: u32, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_whitelisted((token_id, who.clone())), "Account is not whitelisted");
+
+          <Whitelist<T>>::remove((token_id, who.clone()));
+
+          Self::deposit_event(RawEvent::RemovedFromWhitelist(token_id, who));
+
+          Ok(())
+      }
+
+      // schedules the block after which all privileged calls for a token are disabled
+      // once scheduled, the sunset can only be moved earlier, never later
+      // only the owner of the token can schedule the sunset
+      fn schedule_admin_sunset(origin, token_id: u32, block: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          if let Some(sunset) = Self::admin_sunset(token_id) {
+              ensure!(block <= sunset, "Admin sunset cannot be postponed");
+          }
+
+          <AdminSunset<T>>::insert(token_id, block);
+
+          Self::deposit_event(RawEvent::AdminSunsetScheduled(token_id, block));
+
+          Ok(())
+      }
+
+      // queues a transfer intent from the sender
+      // intents are netted against each other and applied by settle_intents
+      fn submit_intent(origin, token_id: u32, to: T::AccountId, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+
+          let mut intents = Self::intents(token_id);
+          // bounded so that settling the queue stays cheap
+          ensure!(intents.len() < Self::max_intents() as usize, "Intent queue of this token is full");
+          let queued = intents.iter().filter(|(from, _, _)| *from == sender).count();
+          ensure!(queued < Self::max_sender_intents() as usize, "Sender has too many queued intents");
+          intents.push((sender.clone(), to.clone(), value));
+          <Intents<T>>::insert(token_id, intents);
+
+          Self::deposit_event(RawEvent::IntentSubmitted(token_id, sender, to, value));
+
+          Ok(())
+      }
+
+      // settles up to `limit` queued intents of a token, oldest first
+      // offsetting flows between the same two accounts are netted and only the residual is transferred
+      // a residual which cannot be transferred is dropped with an event
+      // can be called by anyone
+      fn settle_intents(origin, token_id: u32, limit: u32) -> Result {
+          let _sender = ensure_signed(origin)?;
+
+          let mut intents = Self::intents(token_id);
+          let count = rstd::cmp::min(limit as usize, intents.len());
+          let remaining = intents.split_off(count);
+
+          // net flows per account pair
+          // the pair is keyed in account order, with the flow in each direction
+          let mut flows: BTreeMap<(T::AccountId, T::AccountId), (T::TokenBalance, T::TokenBalance)> = BTreeMap::new();
+          for (from, to, value) in intents {
+              if from < to {
+                  let flow = flows.entry((from, to)).or_insert((Zero::zero(), Zero::zero()));
+                  flow.0 = math::saturating_add(flow.0, value);
+              } else {
+                  let flow = flows.entry((to, from)).or_insert((Zero::zero(), Zero::zero()));
+                  flow.1 = math::saturating_add(flow.1, value);
+              }
+          }
+
+          if remaining.is_empty() {
+              <Intents<T>>::remove(token_id);
+          } else {
+              <Intents<T>>::insert(token_id, remaining);
+          }
+
+          let mut settled = Vec::new();
+          for ((a, b), (forward, backward)) in flows {
+              let (from, to, value) = if forward >= backward {
+                  (a, b, forward - backward)
+              } else {
+                  (b, a, backward - forward)
+              };
+              if value.is_zero() || from == to {
+                  continue;
+              }
+              if Self::_transfer(token_id, from.clone(), to.clone(), value).is_err() {
+                  Self::deposit_event(RawEvent::IntentSettlementFailed(token_id, from, to, value));
+              } else {
+                  settled.push((from, to, value));
+              }
+          }
+          Self::store_receipt(&(token_id, settled));
+
+          Self::deposit_event(RawEvent::IntentsSettled(token_id, count as u32));
+
+          Ok(())
+      }
+
+      // mints new tokens to the owner of the token
+      // only the owner of a mintable token can mint, up to its max supply
+      // tokens for another account are minted through offer_mint, so that the recipient consents
+      fn mint(origin, token_id: u32, amount: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          Self::_mint(token_id, sender, amount)
+      }
+
+      // burns tokens of the sender, reducing the total supply
+      // only allowed if the token is burnable
+      fn burn(origin, token_id: u32, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+          ensure!(Self::token_details(token_id).burnable, "Token is not burnable");
+
+          Self::_burn(token_id, sender, value)
+      }
+
+      // gives the whole balance of the sender to the owner of the token, without the transfer restrictions
+      // frees the slot of the token in the tokens held by the sender, e.g. after unsolicited dust transfers
+      // the supply is unchanged, so a token that is not burnable cannot be made scarcer this way
+      fn discard_balance(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          let owner = Self::owner_of(token_id).ok_or("Token does not exist")?;
+          ensure!(sender != owner, "Owner cannot discard its balance");
+          ensure!(!Self::is_locked_down(sender.clone()), "Sender account is locked down");
+
+          let mut ledger = Ledger::new(token_id);
+          let balance = ledger.balance(&sender);
+          ledger.move_balance(&sender, &owner, balance)?;
+          ledger.deposit_event(RawEvent::BalanceDiscarded(token_id, sender, owner, balance));
+          ledger.commit();
+
+          Ok(())
+      }
+
+      // sets whether holders can burn a token
+      // only the owner of the token can change it
+      fn set_burnable(origin, token_id: u32, burnable: bool) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          let mut token = Self::token_details(token_id);
+          token.burnable = burnable;
+          <Tokens<T>>::insert(token_id, token);
+
+          Self::deposit_event(RawEvent::BurnableChanged(token_id, burnable));
+
+          Ok(())
+      }
+
+      // transfers tokens from the sender to many accounts in one call
+      // all legs are checked before anything is written, so either all succeed or none do
+      // emits a transfer event for every leg
+      fn batch_transfer(origin, token_id: u32, transfers: Vec<(T::AccountId, T::TokenBalance)>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(transfers.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          let transfers: Vec<_> = transfers.into_iter()
+              .map(|(to, value)| (sender.clone(), to, value))
+              .collect();
+          Self::plan_transfers(token_id, &transfers)?.commit();
+          Self::store_receipt(&(token_id, transfers));
+
+          Ok(())
+      }
+
+      // initializes many tokens in one call
+      // takes a name, ticker and total supply for every token
+      // the tokens are neither capped, mintable nor burnable
+      // all tokens are checked before any is created, so either all are created or none
+      fn batch_init(origin, tokens: Vec<(Vec<u8>, Vec<u8>, T::TokenBalance)>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(tokens.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          let mut tickers = Vec::with_capacity(tokens.len());
+          for (name, ticker, total_supply) in tokens.iter() {
+              Self::ensure_can_init(name, ticker, *total_supply, None)?;
+              Self::ensure_ticker_not_reserved(ticker)?;
+              let normalized_ticker = Self::normalize_ticker(ticker);
+              ensure!(!tickers.contains(&normalized_ticker), "Ticker is already registered");
+              tickers.push(normalized_ticker);
+          }
+          Self::ensure_token_ids_available(tokens.len() as u32)?;
+          Self::charge_creation_fee(&sender, tokens.len() as u32)?;
+
+          for (name, ticker, total_supply) in tokens {
+              let token_id = Self::_init(sender.clone(), name.clone(), ticker.clone(), total_supply, None, false, false)?;
+              Self::record_issuer_deposit(token_id, &sender);
+              Self::deposit_event(RawEvent::TokenCreated(token_id, sender.clone(), name, ticker, total_supply));
+          }
+
+          Ok(())
+      }
+
+      // creates a program for migrating holders of an old token to a new token
+      // the new token must be mintable, as swapped tokens are minted
+      // once the deadline has passed, the unswapped old tokens are frozen
+      // can only be called by the root origin (governance)
+      fn create_swap_program(origin, old_token: u32, new_token: u32, numerator: T::TokenBalance, denominator: T::TokenBalance, deadline: T::BlockNumber) -> Result {
+          ensure_root(origin)?;
+          ensure!(old_token != new_token, "Cannot swap a token for itself");
+          ensure!(<Tokens<T>>::exists(old_token), "Token does not exist");
+          ensure!(<Tokens<T>>::exists(new_token), "Token does not exist");
+          ensure!(Self::token_details(new_token).mintable, "Token is not mintable");
+          ensure!(!numerator.is_zero() && !denominator.is_zero(), "Swap rate cannot be zero");
+          ensure!(!<SwapPrograms<T>>::exists(old_token), "Token already has a swap program");
+
+          let program = SwapProgram {
+              new_token,
+              numerator,
+              denominator,
+              deadline,
+          };
+          <SwapPrograms<T>>::insert(old_token, program);
+
+          Self::deposit_event(RawEvent::SwapProgramCreated(old_token, new_token, deadline));
+
+          Ok(())
+      }
+
+      // swaps old tokens of the sender for new tokens at the program rate
+      // burns the old tokens and mints the new ones
+      fn swap(origin, old_token: u32, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let program = Self::swap_program(old_token).ok_or("Token has no swap program")?;
+          ensure!(<system::Module<T>>::block_number() <= program.deadline, "Swap program has ended");
+
+          let new_value = math::mul_div(value, program.numerator, program.denominator)?;
+          ensure!(!new_value.is_zero(), "Swap amount is too small");
+
+          // swapping must not escape a pause, freeze or blacklist of the old token
+          Self::ensure_can_transfer(old_token, &sender, &sender)?;
+          // check the burn first, minting validates before it writes
+          Self::ensure_can_burn(old_token, &sender, value)?;
+          Self::_mint(program.new_token, sender.clone(), new_value)?;
+          Self::_burn(old_token, sender.clone(), value)?;
+          Self::store_receipt(&(old_token, program.new_token, sender.clone(), value, new_value));
+
+          Self::deposit_event(RawEvent::Swapped(old_token, program.new_token, sender, value, new_value));
+
+          Ok(())
+      }
+
+      // transfers the whole balance of the sender to another account
+      // the emptied balance entry of the sender is removed
+      fn transfer_all(origin, token_id: u32, to: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(sender != to, "Cannot transfer to the same account");
+          let value = Self::balance_of(token_id, &sender);
+          Self::_transfer(token_id, sender, to, value)
+      }
+
+      // transfers tokens with an attached memo, e.g. an exchange deposit reference
+      // the memo is only included in the event, it is not stored
+      fn transfer_with_memo(origin, token_id: u32, to: T::AccountId, value: T::TokenBalance, memo: Vec<u8>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(memo.len() <= Self::max_memo_length() as usize, "memo is too long");
+          Self::_transfer(token_id, sender.clone(), to.clone(), value)?;
+
+          Self::deposit_event(RawEvent::TransferWithMemo(token_id, sender, to, value, memo));
+
+          Ok(())
+      }
+
+      // approve token transfer like approve, with the allowance expiring at a block number
+      // transfer_from rejects spends from the expiry block onwards
+      fn approve_until(origin, token_id: u32, spender: T::AccountId, value: T::TokenBalance, expires_at: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_can_approve(token_id, &sender, &spender)?;
+          ensure!(Self::has_balance(token_id, &sender), "Account does not own this token");
+          ensure!(expires_at > <system::Module<T>>::block_number(), "Expiry must be in the future");
+
+          let allowance = Self::allowance(token_id, &sender, &spender);
+          let updated_allowance = math::checked_add(allowance, value, "overflow in calculating allowance")?;
+          Self::write_allowance(token_id, &sender, &spender, updated_allowance);
+          <AllowanceExpiry<T>>::insert((token_id, sender.clone(), spender.clone()), expires_at);
+
+          Self::deposit_event(RawEvent::Approval(token_id, sender.clone(), spender.clone(), value));
+          Self::deposit_event(RawEvent::ApprovalExpiry(token_id, sender, spender, expires_at));
+
+          Ok(())
+      }
+
+      // sets an allowance from an approval signed off-chain by the owner
+      // can be submitted by anyone, e.g. a relayer paying the fee
+      // the signed message is the encoded (b"erc20:permit", genesis hash, token_id, owner, spender, value, nonce, deadline)
+      // the nonce of the owner is consumed, so the signature cannot be replayed
+      fn permit(origin, token_id: u32, owner: T::AccountId, spender: T::AccountId, value: T::TokenBalance, deadline: T::BlockNumber, signature: T::Signature) -> Result {
+          let _sender = ensure_signed(origin)?;
+          ensure!(<system::Module<T>>::block_number() <= deadline, "Permit has expired");
+          Self::ensure_can_approve(token_id, &owner, &spender)?;
+          ensure!(Self::has_balance(token_id, &owner), "Account does not own this token");
+
+          let nonce = Self::nonce_of(owner.clone());
+          let message = (&b"erc20:permit"[..], Self::signing_domain(), token_id, owner.clone(), spender.clone(), value, nonce, deadline).encode();
+          ensure!(signature.verify(&message[..], &owner), "Invalid signature");
+          Self::use_nonce(&owner, nonce)?;
+
+          Self::write_allowance(token_id, &owner, &spender, value);
+          <AllowanceExpiry<T>>::remove((token_id, owner.clone(), spender.clone()));
+
+          Self::deposit_event(RawEvent::Approval(token_id, owner, spender, value));
+
+          Ok(())
+      }
+
+      // transfers tokens with a context tag of the caller, e.g. an order or invoice id
+      // the context is only included in the transfer event, it is not stored
+      fn transfer_with_context(origin, token_id: u32, to: T::AccountId, value: T::TokenBalance, context: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::_transfer_with_context(token_id, sender, to, value, Some(context))
+      }
+
+      // transfers tokens of the signer, authorized by a message signed off-chain
+      // can be submitted by anyone, e.g. a relayer paying the fee
+      // the signed message is the encoded (b"erc20:transfer", genesis hash, token_id, signer, to, value, nonce)
+      // the nonce must be the current nonce of the signer and is consumed
+      fn transfer_by_signature(origin, token_id: u32, signer: T::AccountId, to: T::AccountId, value: T::TokenBalance, nonce: u64, signature: T::Signature) -> Result {
+          let _sender = ensure_signed(origin)?;
+          ensure!(nonce == Self::nonce_of(signer.clone()), "Invalid nonce");
+
+          let message = (&b"erc20:transfer"[..], Self::signing_domain(), token_id, signer.clone(), to.clone(), value, nonce).encode();
+          ensure!(signature.verify(&message[..], &signer), "Invalid signature");
+
+          Self::_transfer(token_id, signer.clone(), to, value)?;
+          Self::use_nonce(&signer, nonce)
+      }
+
+      // transfers tokens of the signer to many accounts, authorized by one message signed off-chain
+      // can be submitted by anyone, e.g. a relayer running payroll
+      // the signed message is the encoded (b"erc20:batch", genesis hash, token_id, signer, hash of the transfers, nonce)
+      // the nonce must be the current nonce of the signer and is consumed once for the whole batch
+      // all legs are checked before anything is written, so either all succeed or none do
+      fn batch_transfer_by_signature(origin, token_id: u32, signer: T::AccountId, transfers: Vec<(T::AccountId, T::TokenBalance)>, nonce: u64, signature: T::Signature) -> Result {
+          let _sender = ensure_signed(origin)?;
+          ensure!(transfers.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+          ensure!(nonce == Self::nonce_of(signer.clone()), "Invalid nonce");
+
+          let batch_hash = T::Hashing::hash_of(&transfers);
+          let message = (&b"erc20:batch"[..], Self::signing_domain(), token_id, signer.clone(), batch_hash, nonce).encode();
+          ensure!(signature.verify(&message[..], &signer), "Invalid signature");
+
+          let transfers: Vec<_> = transfers.into_iter()
+              .map(|(to, value)| (signer.clone(), to, value))
+              .collect();
+          Self::plan_transfers(token_id, &transfers)?.commit();
+          Self::store_receipt(&(token_id, transfers));
+
+          Self::use_nonce(&signer, nonce)
+      }
+
+      // sets the minimum balance of a token, mirroring the existential deposit of the balances module
+      // transfers cannot leave the receiver below it
+      // a sender balance left below it is removed as dust
+      // only the owner of the token can set it
+      fn set_min_balance(origin, token_id: u32, min_balance: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          <MinBalance<T>>::insert(token_id, min_balance);
+
+          Self::deposit_event(RawEvent::MinBalanceChanged(token_id, min_balance));
+
+          Ok(())
+      }
+
+      // delegates a portion of the allowance the sender has from an owner to a sub-spender
+      // the delegated value is taken out of the allowance of the sender
+      // sub-allowances die with the root approval when the owner revokes it
+      fn sub_approve(origin, token_id: u32, owner: T::AccountId, sub_spender: T::AccountId, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_can_approve(token_id, &owner, &sub_spender)?;
+          Self::ensure_allowance_active(token_id, &owner, &sender)?;
+
+          let allowance = Self::allowance(token_id, &owner, &sender);
+          ensure!(allowance >= value, "Not enough allowance.");
+          let updated_allowance = math::checked_sub(allowance, value, "overflow in calculating allowance")?;
+
+          let mut sub_spenders = Self::sub_spenders((token_id, owner.clone(), sender.clone()));
+          if !sub_spenders.contains(&sub_spender) {
+              ensure!(sub_spenders.len() < Self::max_batch_size() as usize, "Spender has too many sub-spenders");
+              sub_spenders.push(sub_spender.clone());
+          }
+
+          let epoch = Self::allowance_epoch((token_id, owner.clone(), sender.clone()));
+          let key = (token_id, owner.clone(), sender.clone(), sub_spender.clone());
+          let current = Self::sub_allowance(key.clone());
+          // a sub-allowance from a revoked root approval is worthless, it is replaced
+          let current_value = if current.epoch == epoch { current.value } else { Zero::zero() };
+          let sub_allowance = SubAllowance {
+              value: math::checked_add(current_value, value, "overflow in calculating allowance")?,
+              epoch,
+          };
+
+          Self::write_allowance(token_id, &owner, &sender, updated_allowance);
+          <SubAllowances<T>>::insert(key, sub_allowance);
+          <SubSpenders<T>>::insert((token_id, owner.clone(), sender.clone()), sub_spenders);
+
+          Self::deposit_event(RawEvent::SubApproval(token_id, owner, sender, sub_spender, value));
+
+          Ok(())
+      }
+
+      // transfers tokens of an owner using a sub-allowance delegated by one of its spenders
+      fn transfer_from_sub(origin, token_id: u32, owner: T::AccountId, spender: T::AccountId, to: T::AccountId, value: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_allowance_active(token_id, &owner, &spender)?;
+
+          let key = (token_id, owner.clone(), spender.clone(), sender.clone());
+          ensure!(<SubAllowances<T>>::exists(key.clone()), "Allowance does not exist.");
+          let mut sub_allowance = Self::sub_allowance(key.clone());
+          ensure!(sub_allowance.epoch == Self::allowance_epoch((token_id, owner.clone(), spender.clone())), "Allowance has been revoked.");
+          ensure!(sub_allowance.value >= value, "Not enough allowance.");
+
+          Self::_transfer(token_id, owner, to, value)?;
+
+          sub_allowance.value = math::checked_sub(sub_allowance.value, value, "overflow in calculating allowance")?;
+          <SubAllowances<T>>::insert(key, sub_allowance);
+
+          Ok(())
+      }
+
+      // revokes the allowance of a spender and every sub-allowance delegated from it
+      fn revoke_allowance(origin, token_id: u32, spender: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::_revoke_allowance(token_id, sender, spender);
+
+          Ok(())
+      }
+
+      // revokes all the allowances the sender has given for a token
+      // sub-allowances delegated from them die with them
+      fn revoke_all_allowances(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+
+          for spender in Self::approved_spenders((token_id, sender.clone())) {
+              Self::_revoke_allowance(token_id, sender.clone(), spender);
+          }
+
+          Ok(())
+      }
+
+      // schedules binding a token to a logic module of the given kind, or unbinding it with none
+      // the change only takes effect after the logic timelock, so holders can react to it
+      fn schedule_logic(origin, token_id: u32, kind: LogicKind, module_id: Option<u32>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          if let Some(id) = module_id {
+              ensure!(T::LogicModules::exists(kind, id), "Logic module does not exist");
+          }
+
+          let effective = <system::Module<T>>::block_number() + Self::logic_timelock();
+          <PendingLogic<T>>::insert((token_id, kind), (module_id, effective.clone()));
+
+          Self::deposit_event(RawEvent::LogicScheduled(token_id, kind, module_id, effective));
+
+          Ok(())
+      }
+
+      // cancels a scheduled logic module change
+      fn cancel_logic(origin, token_id: u32, kind: LogicKind) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(<PendingLogic<T>>::exists((token_id, kind)), "No logic change is scheduled");
+
+          <PendingLogic<T>>::remove((token_id, kind));
+
+          Ok(())
+      }
+
+      // applies a scheduled logic module change once its timelock has passed
+      // can be called by anyone
+      fn apply_logic(origin, token_id: u32, kind: LogicKind) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let (module_id, effective) = Self::pending_logic((token_id, kind)).ok_or("No logic change is scheduled")?;
+          ensure!(<system::Module<T>>::block_number() >= effective, "Logic change is still timelocked");
+
+          <PendingLogic<T>>::remove((token_id, kind));
+          match module_id {
+              Some(id) => <TokenLogic<T>>::insert((token_id, kind), id),
+              None => <TokenLogic<T>>::remove((token_id, kind)),
+          }
+
+          Self::deposit_event(RawEvent::LogicChanged(token_id, kind, module_id));
+
+          Ok(())
+      }
+
+      // sets the balance below which the sender is notified with a low balance event
+      // none removes the threshold
+      fn set_low_balance_threshold(origin, token_id: u32, threshold: Option<T::TokenBalance>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+
+          match threshold {
+              Some(threshold) => <LowBalanceThreshold<T>>::insert((token_id, sender), threshold),
+              None => <LowBalanceThreshold<T>>::remove((token_id, sender)),
+          }
+
+          Ok(())
+      }
+
+      // writes the balances of the holders of a token into a new child trie
+      // writes up to max holders per call, bounded by the max batch size, continuing where the last call stopped
+      // a snapshot of more than one page needs the token to be paused, so that the holders do not change between pages
+      // once every holder is written, the root of the child trie is stored, so that balance proofs
+      // against the snapshot can be verified by light clients
+      fn snapshot_balances(origin, token_id: u32, max: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          let (snapshot_id, start, next_id) = match Self::snapshot_cursor(token_id) {
+              Some((snapshot_id, start)) => (snapshot_id, start, None),
+              None => {
+                  let snapshot_id = Self::snapshot_count(token_id);
+                  let next_id = snapshot_id.checked_add(1).ok_or("overflow in calculating snapshot id")?;
+                  (snapshot_id, 0, Some(next_id))
+              },
+          };
+          let holder_count = Self::holder_count(token_id);
+          let end = start.saturating_add(max.min(Self::max_batch_size())).min(holder_count);
+          ensure!((start == 0 && end == holder_count) || Self::is_paused(token_id), "Token must be paused for a snapshot of more than one page");
+
+          if let Some(next_id) = next_id {
+              <SnapshotCount<T>>::insert(token_id, next_id);
+          }
+          Self::write_snapshot(token_id, snapshot_id, start, end);
+
+          if end < holder_count {
+              <SnapshotCursor<T>>::insert(token_id, (snapshot_id, end));
+              Self::deposit_event(RawEvent::SnapshotProgressed(token_id, snapshot_id, end, holder_count));
+          } else {
+              <SnapshotCursor<T>>::remove(token_id);
+              let root = runtime_io::child_storage_root(&Self::snapshot_storage_key(token_id, snapshot_id)).unwrap_or_default();
+              <Snapshots<T>>::insert((token_id, snapshot_id), (<system::Module<T>>::block_number(), root.clone()));
+              Self::deposit_event(RawEvent::SnapshotTaken(token_id, snapshot_id, root));
+          }
+
+          Ok(())
+      }
+
+      // starts destroying a token
+      // transfers, approvals and minting stop, the balances and approvals are then cleared
+      // in steps with destroy_accounts and destroy_approvals before finish_destroy
+      // tokens in a liquidity pool, on sale or issued along a bonding curve cannot be destroyed,
+      // as their balances back the positions of other accounts
+      fn start_destroy(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+          ensure!(!Self::is_pooled(token_id), "Token is in a liquidity pool");
+          ensure!(!<Sales<T>>::exists(token_id), "Token has a sale");
+          ensure!(!<BondingCurves<T>>::exists(token_id), "Token has a bonding curve");
+
+          <Destroying<T>>::insert(token_id, true);
+
+          Self::deposit_event(RawEvent::DestroyStarted(token_id));
+
+          Ok(())
+      }
+
+      // removes the balances of up to max holders of a token being destroyed
+      // both the free and the reserved balances are removed, as a reserved balance keeps its account a holder
+      // the removed balances are taken out of the total supply
+      fn destroy_accounts(origin, token_id: u32, max: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_destroying(token_id), "Token is not being destroyed");
+
+          let mut token = Self::token_details(token_id);
+          let mut removed = 0;
+          while removed < max.min(Self::max_batch_size()) && Self::holder_count(token_id) > 0 {
+              let who = Self::holder_at((token_id, Self::holder_count(token_id) - 1));
+              let balance = math::saturating_add(Self::balance_of(token_id, &who), Self::reserved_balance_of((token_id, who.clone())));
+              token.total_supply = math::saturating_sub(token.total_supply, balance);
+              Self::set_balance(token_id, &who, Zero::zero());
+              Self::set_reserved_balance(token_id, &who, Zero::zero());
+              Self::clear_holder_state(token_id, &who);
+              removed += 1;
+          }
+          <Tokens<T>>::insert(token_id, token);
+
+          Self::deposit_event(RawEvent::AccountsDestroyed(token_id, removed, Self::holder_count(token_id)));
+
+          Ok(())
+      }
+
+      // removes all the approvals given by the given owners for a token being destroyed
+      fn destroy_approvals(origin, token_id: u32, owners: Vec<T::AccountId>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_destroying(token_id), "Token is not being destroyed");
+          ensure!(owners.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          for owner in owners {
+              for spender in Self::approved_spenders((token_id, owner.clone())) {
+                  Self::_revoke_allowance(token_id, owner.clone(), spender.clone());
+                  <AllowanceEpoch<T>>::remove((token_id, owner.clone(), spender));
+              }
+          }
+
+          Self::deposit_event(RawEvent::ApprovalsDestroyed(token_id, Self::approval_count(token_id)));
+
+          Ok(())
+      }
+
+      // refunds the given escrows and hashed timelocks of a token being destroyed to their payers
+      // escrows and hashed timelocks are not indexed by token, so their ids are taken from the events
+      // ids of other tokens are skipped
+      fn destroy_holds(origin, token_id: u32, escrows: Vec<u64>, htlcs: Vec<H256>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_destroying(token_id), "Token is not being destroyed");
+          ensure!(escrows.len().saturating_add(htlcs.len()) <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          for escrow_id in escrows {
+              if let Some(escrow) = Self::escrow(escrow_id).filter(|escrow| escrow.token_id == token_id) {
+                  // a refund that cannot be made leaves the tokens on the escrow account, where destroy_accounts removes them
+                  if Self::_refund_escrow(escrow_id, escrow).is_err() {
+                      Self::close_escrow(escrow_id, token_id);
+                  }
+              }
+          }
+          for hash in htlcs {
+              if let Some(htlc) = Self::htlc(hash).filter(|htlc| htlc.token_id == token_id) {
+                  Self::_refund_htlc(hash, htlc);
+              }
+          }
+
+          Self::deposit_event(RawEvent::HoldsDestroyed(token_id, Self::open_holds(token_id)));
+
+          Ok(())
+      }
+
+      // removes the given unclaimed ethereum balances of a token being destroyed
+      // the removed balances are taken out of the total supply
+      fn destroy_claims(origin, token_id: u32, addresses: Vec<EthereumAddress>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_destroying(token_id), "Token is not being destroyed");
+          ensure!(addresses.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          let mut token = Self::token_details(token_id);
+          for address in addresses {
+              if let Some(value) = <EthereumClaims<T>>::take((token_id, address)) {
+                  token.total_supply = math::saturating_sub(token.total_supply, value);
+                  <UnclaimedCount<T>>::mutate(token_id, |count| *count = count.saturating_sub(1));
+              }
+          }
+          <Tokens<T>>::insert(token_id, token);
+
+          Self::deposit_event(RawEvent::ClaimsDestroyed(token_id, Self::unclaimed_count(token_id)));
+
+          Ok(())
+      }
+
+      // removes a token once all its balances, approvals, escrows, hashed timelocks, claims and mint offers are cleared
+      // other reserved balances are removed along with the holders
+      // pending mint offers are withdrawn by the owner with cancel_mint_offer
+      // the ticker of the token can be registered again
+      fn finish_destroy(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(Self::is_destroying(token_id), "Token is not being destroyed");
+          ensure!(Self::holder_count(token_id) == 0, "Token still has holders");
+          ensure!(Self::approval_count(token_id) == 0, "Token still has approvals");
+          ensure!(Self::reserved_supply(token_id).is_zero(), "Token still has reserved balances");
+          ensure!(Self::open_holds(token_id) == 0, "Token still has escrows or hashed timelocks");
+          ensure!(Self::unclaimed_count(token_id) == 0, "Token still has unclaimed balances");
+          ensure!(Self::mint_offer_count(token_id) == 0, "Token still has mint offers");
+          ensure!(!<ModerationProposals<T>>::exists(token_id), "Token has a moderation proposal");
+
+          let metadata = Self::metadata(token_id);
+          <TickerRegistry<T>>::remove(Self::normalize_ticker(&metadata.ticker));
+          <Tokens<T>>::remove(token_id);
+          <Metadata<T>>::remove(token_id);
+          <Owners<T>>::remove(token_id);
+          <OwnedTokens<T>>::mutate(sender.clone(), |tokens| tokens.retain(|t| *t != token_id));
+          <MinBalance<T>>::remove(token_id);
+          <Destroying<T>>::remove(token_id);
+          Self::clear_token_state(token_id);
+          if let Some((issuer, deposit)) = <IssuerDeposits<T>>::take(token_id) {
+              let _ = <balances::Module<T>>::unreserve(&issuer, deposit);
+          }
+
+          Self::deposit_event(RawEvent::TokenDestroyed(token_id));
+
+          Ok(())
+      }
+
+      // registers the account that can lock down all the token balances of the sender
+      // none removes the lockdown guardian
+      fn set_lockdown_guardian(origin, guardian: Option<T::AccountId>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(!Self::is_locked_down(sender.clone()), "Account is locked down");
+
+          match guardian {
+              Some(guardian) => <LockdownGuardian<T>>::insert(sender, guardian),
+              None => <LockdownGuardian<T>>::remove(sender),
+          }
+
+          Ok(())
+      }
+
+      // locks down all the token balances of an account, across every token
+      // can only be called by the lockdown guardian of the account
+      // a pending request to lift the lockdown is cancelled
+      fn panic_freeze(origin, who: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(Self::lockdown_guardian(who.clone()) == Some(sender), "Sender is not the lockdown guardian of the account");
+
+          <LockedDown<T>>::insert(who.clone(), true);
+          <UnlockAt<T>>::remove(who.clone());
+
+          Self::deposit_event(RawEvent::LockedDown(who));
+
+          Ok(())
+      }
+
+      // requests lifting the lockdown of the sender
+      // the lockdown can be lifted once the lockdown delay has passed
+      fn request_unlock(origin) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(Self::is_locked_down(sender.clone()), "Account is not locked down");
+
+          let unlock_at = <system::Module<T>>::block_number() + Self::lockdown_delay();
+          <UnlockAt<T>>::insert(sender.clone(), unlock_at.clone());
+
+          Self::deposit_event(RawEvent::UnlockRequested(sender, unlock_at));
+
+          Ok(())
+      }
+
+      // lifts the lockdown of the sender after the requested delay
+      fn unlock(origin) -> Result {
+          let sender = ensure_signed(origin)?;
+          let unlock_at = Self::unlock_at(sender.clone()).ok_or("Unlock has not been requested")?;
+          ensure!(<system::Module<T>>::block_number() >= unlock_at, "Unlock delay has not passed");
+
+          <LockedDown<T>>::remove(sender.clone());
+          <UnlockAt<T>>::remove(sender.clone());
+
+          Self::deposit_event(RawEvent::Unlocked(sender));
+
+          Ok(())
+      }
+
+      // updates the name and ticker of a token
+      // the same length, uniqueness and reserved prefix checks as in init apply to the new ticker
+      fn set_metadata(origin, token_id: u32, name: Vec<u8>, ticker: Vec<u8>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_metadata_frozen(token_id), "Token metadata is frozen");
+
+          Self::write_name_and_ticker(token_id, name, ticker)
+      }
+
+      // makes the name, ticker, uri and metadata hash of a token immutable
+      // cannot be undone
+      fn freeze_metadata(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          <FrozenMetadata<T>>::insert(token_id, true);
+
+          Self::deposit_event(RawEvent::MetadataFrozen(token_id));
+
+          Ok(())
+      }
+
+      // grants the metadata manager role of a token to an account, none revokes it
+      // the manager can only update the uri and metadata hash, nothing financial
+      fn set_metadata_manager(origin, token_id: u32, manager: Option<T::AccountId>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          match manager.clone() {
+              Some(manager) => <MetadataManagers<T>>::insert(token_id, manager),
+              None => <MetadataManagers<T>>::remove(token_id),
+          }
+
+          Self::deposit_event(RawEvent::MetadataManagerSet(token_id, manager));
+
+          Ok(())
+      }
+
+      // sets the link to off-chain info of a token, such as its logo and description
+      // none removes the link
+      // can be called by the owner or the metadata manager of the token
+      fn set_uri(origin, token_id: u32, uri: Option<Vec<u8>>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_metadata_editor(token_id, &sender)?;
+          ensure!(!Self::is_metadata_frozen(token_id), "Token metadata is frozen");
+          if let Some(uri) = uri.as_ref() {
+              ensure!(uri.len() <= Self::max_uri_length() as usize, "token uri is too long");
+          }
+
+          <Metadata<T>>::mutate(token_id, |metadata| metadata.uri = uri.clone());
+
+          Self::deposit_event(RawEvent::UriUpdated(token_id, uri));
+
+          Ok(())
+      }
+
+      // commits the hash of the off-chain info of a token
+      // none removes the hash
+      // can be called by the owner or the metadata manager of the token
+      fn set_metadata_hash(origin, token_id: u32, metadata_hash: Option<H256>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_metadata_editor(token_id, &sender)?;
+          ensure!(!Self::is_metadata_frozen(token_id), "Token metadata is frozen");
+
+          let mut metadata = Self::metadata(token_id);
+          metadata.metadata_hash = metadata_hash;
+          let (name, ticker) = (metadata.name.clone(), metadata.ticker.clone());
+          <Metadata<T>>::insert(token_id, metadata);
+
+          Self::deposit_event(RawEvent::MetadataUpdated(token_id, name, ticker, metadata_hash));
+
+          Ok(())
+      }
+
+      // sets the number of decimal places of a token
+      // prices of sales and bonding curves and the first deposit into a pool depend on the decimals,
+      // so they cannot change while the token has any of them
+      fn set_decimals(origin, token_id: u32, decimals: u8) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!Self::is_metadata_frozen(token_id), "Token metadata is frozen");
+          ensure!(decimals <= MAX_DECIMALS, "Token has too many decimals");
+          ensure!(!Self::is_pooled(token_id), "Token is in a liquidity pool");
+          ensure!(!<Sales<T>>::exists(token_id), "Token has a sale");
+          ensure!(!<BondingCurves<T>>::exists(token_id), "Token has a bonding curve");
+
+          <Metadata<T>>::mutate(token_id, |metadata| metadata.decimals = decimals);
+
+          Self::deposit_event(RawEvent::DecimalsSet(token_id, decimals));
+
+          Ok(())
+      }
+
+      // claims the balance of a migrated token held by an ethereum address
+      // the signature is an ethereum personal message signature of the claiming account
+      fn claim(origin, token_id: u32, signature: EcdsaSignature) -> Result {
+          let sender = ensure_signed(origin)?;
+          let address = Self::ethereum_signer(&sender, &signature).ok_or("Invalid ethereum signature")?;
+          let value = Self::ethereum_claim((token_id, address)).ok_or("Ethereum address has nothing to claim")?;
+          let balance = Self::balance_of(token_id, &sender);
+          let updated_balance = math::checked_add(balance, value, "overflow in calculating balance")?;
+          Self::ensure_can_receive(token_id, &sender)?;
+
+          <EthereumClaims<T>>::remove((token_id, address));
+          <UnclaimedCount<T>>::mutate(token_id, |count| *count = count.saturating_sub(1));
+          Self::set_balance(token_id, &sender, updated_balance);
+
+          Self::deposit_event(RawEvent::Claimed(token_id, address, sender, value));
+
+          Ok(())
+      }
+
+      // sets whether the transfer fees of a token are paid to the treasury of the chain
+      // instead of the token owner
+      fn set_fees_to_treasury(origin, token_id: u32, to_treasury: bool) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          <FeesToTreasury<T>>::insert(token_id, to_treasury);
+
+          Self::deposit_event(RawEvent::FeeDestinationChanged(token_id, to_treasury));
+
+          Ok(())
+      }
+
+      // sets the parts per million of every transfer of a token that are burned instead of delivered
+      // zero turns burning on transfer off
+      fn set_transfer_burn_rate(origin, token_id: u32, rate: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(rate <= 1_000_000, "Burn rate cannot exceed one million parts per million");
+
+          <TransferBurnRate<T>>::insert(token_id, rate);
+
+          Self::deposit_event(RawEvent::TransferBurnRateChanged(token_id, rate));
+
+          Ok(())
+      }
+
+      // sets how the transfer burn and the pool swap fees charged in a token are rounded
+      // none keeps the rounding each charge has by default, down for the burn and up for the swap fee
+      fn set_rounding_policy(origin, token_id: u32, rounding: Option<Rounding>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          match rounding {
+              Some(rounding) => <RoundingPolicy<T>>::insert(token_id, rounding),
+              None => <RoundingPolicy<T>>::remove(token_id),
+          }
+
+          Self::deposit_event(RawEvent::RoundingPolicyChanged(token_id, rounding));
+
+          Ok(())
+      }
+
+      // grants a borrower a credit line, or changes the limit of the one already granted
+      // transfers of the borrower beyond its balance are covered by the sender up to the limit
+      // a borrower has at most one credit line per token
+      fn grant_credit_line(origin, token_id: u32, borrower: T::AccountId, limit: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+          ensure!(sender != borrower, "Cannot grant a credit line to yourself");
+
+          let line = match Self::credit_line((token_id, borrower.clone())) {
+              Some(line) => {
+                  ensure!(line.grantor == sender, "Borrower already has a credit line");
+                  CreditLine { limit, ..line }
+              },
+              None => CreditLine { grantor: sender.clone(), limit, debt: Zero::zero() },
+          };
+          <CreditLines<T>>::insert((token_id, borrower.clone()), line);
+
+          Self::deposit_event(RawEvent::CreditLineGranted(token_id, sender, borrower, limit));
+
+          Ok(())
+      }
+
+      // closes a credit line once its debt is repaid
+      fn close_credit_line(origin, token_id: u32, borrower: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          let line = Self::credit_line((token_id, borrower.clone())).ok_or("Credit line does not exist")?;
+          ensure!(line.grantor == sender, "Sender is not the grantor of the credit line");
+          ensure!(line.debt.is_zero(), "Credit line still has debt");
+
+          <CreditLines<T>>::remove((token_id, borrower.clone()));
+
+          Self::deposit_event(RawEvent::CreditLineClosed(token_id, sender, borrower));
+
+          Ok(())
+      }
+
+      // proposes buying out the other holders of a token at a fixed price in a payout token
+      // holders can record their dissent until the end of the window
+      fn propose_buyout(origin, token_id: u32, payout_token: u32, numerator: T::TokenBalance, denominator: T::TokenBalance, window_end: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(<Tokens<T>>::exists(payout_token), "Token does not exist");
+          ensure!(payout_token != token_id, "Payout token must differ from the bought out token");
+          ensure!(!denominator.is_zero(), "Price denominator cannot be zero");
+          ensure!(window_end > <system::Module<T>>::block_number(), "Dissent window must end in the future");
+          ensure!(!<Buyouts<T>>::exists(token_id), "Token already has a buyout");
+
+          let round = Self::buyout_rounds(token_id);
+          <BuyoutRounds<T>>::insert(token_id, round.wrapping_add(1));
+          <Buyouts<T>>::insert(token_id, Buyout { payout_token, numerator, denominator, window_end: window_end.clone(), approved: false, round });
+
+          Self::deposit_event(RawEvent::BuyoutProposed(token_id, payout_token, numerator, denominator, window_end));
+
+          Ok(())
+      }
+
+      // approves a proposed buyout
+      // can only be called by the force origin (governance), before the dissent window ends
+      fn approve_buyout(origin, token_id: u32) -> Result {
+          T::ForceOrigin::ensure_origin(origin)?;
+          let mut buyout = Self::buyout(token_id).ok_or("Token has no buyout")?;
+          ensure!(<system::Module<T>>::block_number() <= buyout.window_end, "Dissent window has ended");
+
+          buyout.approved = true;
+          <Buyouts<T>>::insert(token_id, buyout);
+
+          Self::deposit_event(RawEvent::BuyoutApproved(token_id));
+
+          Ok(())
+      }
+
+      // withdraws a buyout, e.g. after too much dissent
+      fn cancel_buyout(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(<Buyouts<T>>::exists(token_id), "Token has no buyout");
+
+          <Buyouts<T>>::remove(token_id);
+
+          Self::deposit_event(RawEvent::BuyoutCancelled(token_id));
+
+          Ok(())
+      }
+
+      // records the dissent of a holder with the buyout of a token
+      // the dissent is recorded for the record, it does not exempt the holder from the buyout
+      fn dissent(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          let buyout = Self::buyout(token_id).ok_or("Token has no buyout")?;
+          ensure!(<system::Module<T>>::block_number() <= buyout.window_end, "Dissent window has ended");
+          ensure!(Self::has_balance(token_id, &sender), "Account does not own this token");
+          ensure!(!Self::has_dissented((token_id, buyout.round, sender.clone())), "Account has already dissented");
+
+          let balance = Self::balance_of(token_id, &sender);
+          <Dissents<T>>::insert((token_id, buyout.round, sender.clone()), true);
+          <DissentingBalance<T>>::mutate((token_id, buyout.round), |total| *total = math::saturating_add(*total, balance));
+
+          Self::deposit_event(RawEvent::Dissented(token_id, sender, balance));
+
+          Ok(())
+      }
+
+      // exchanges the whole balance of the given holders for payout tokens paid by the owner
+      // only once the buyout is approved and the dissent window has ended, until the execution period ends
+      // the buyout is completed once the owner holds the whole supply
+      fn execute_buyout(origin, token_id: u32, holders: Vec<T::AccountId>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(holders.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+          let buyout = Self::buyout(token_id).ok_or("Token has no buyout")?;
+          ensure!(buyout.approved, "Buyout is not approved");
+          let now = <system::Module<T>>::block_number();
+          ensure!(now > buyout.window_end, "Dissent window has not ended");
+          ensure!(now <= math::saturating_add(buyout.window_end.clone(), Self::buyout_execution_period()), "Buyout has expired");
+          ensure!(!Self::is_locked_down(sender.clone()), "Sender account is locked down");
+
+          // every leg is checked on the ledgers first, so that nothing is written if any leg fails
+          // the legs get the checks of a bare move, locks and lockdowns, but not the transfer restrictions
+          let mut bought = Ledger::new(token_id);
+          let mut paid = Ledger::new(buyout.payout_token);
+          let mut bought_out = Vec::with_capacity(holders.len());
+          for who in holders {
+              if who == sender || bought_out.contains(&who) {
+                  continue;
+              }
+              let value = bought.balance(&who);
+              if value.is_zero() {
+                  continue;
+              }
+              ensure!(!Self::is_locked_down(who.clone()), "Holder account is locked down");
+              bought.move_balance(&who, &sender, value)?;
+              bought.record_last_move();
+
+              let payout = math::mul_div(value, buyout.numerator, buyout.denominator)?;
+              if !payout.is_zero() {
+                  paid.move_balance(&sender, &who, payout)?;
+                  paid.record_last_move();
+              }
+              bought.deposit_event(RawEvent::SqueezedOut(token_id, who.clone(), value, payout));
+              bought_out.push(who);
+          }
+          let completed = bought.balance(&sender) == Self::token_details(token_id).total_supply;
+
+          bought.commit();
+          paid.commit();
+          if completed {
+              <Buyouts<T>>::remove(token_id);
+              Self::deposit_event(RawEvent::BuyoutCompleted(token_id));
+          }
+
+          Ok(())
+      }
+
+      // closes a buyout that was not completed within its execution period
+      // anyone can close it, so that an expired buyout does not block a new one
+      fn close_buyout(origin, token_id: u32) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let buyout = Self::buyout(token_id).ok_or("Token has no buyout")?;
+          let execution_end = math::saturating_add(buyout.window_end, Self::buyout_execution_period());
+          ensure!(<system::Module<T>>::block_number() > execution_end, "Buyout has not expired");
+
+          <Buyouts<T>>::remove(token_id);
+
+          Self::deposit_event(RawEvent::BuyoutExpired(token_id));
+
+          Ok(())
+      }
+
+      // parks tokens of the sender in an escrow for the beneficiary
+      // the tokens are moved to the escrow account until the escrow is released or refunded,
+      // so that they are apart from the payer's balance and cannot be caught up in its recovery or destroy steps
+      fn create_escrow(origin, token_id: u32, beneficiary: T::AccountId, amount: T::TokenBalance, arbiter: T::AccountId) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(!amount.is_zero(), "Escrow amount cannot be zero");
+          ensure!(sender != beneficiary, "Payer cannot be the beneficiary of an escrow");
+          Self::ensure_can_transfer(token_id, &sender, &beneficiary)?;
+
+          let escrow_id = Self::escrow_id();
+          let next_escrow_id = escrow_id.checked_add(1).ok_or("overflow in calculating next escrow id")?;
+          Self::_move(token_id, sender.clone(), Self::escrow_account(escrow_id)?, amount)?;
+
+          <EscrowId<T>>::put(next_escrow_id);
+          let escrow = Escrow {
+              token_id,
+              payer: sender.clone(),
+              beneficiary: beneficiary.clone(),
+              arbiter: arbiter.clone(),
+              amount,
+          };
+          <Escrows<T>>::insert(escrow_id, escrow);
+          <OpenHolds<T>>::mutate(token_id, |count| *count = count.saturating_add(1));
+
+          Self::deposit_event(RawEvent::EscrowCreated(escrow_id, token_id, sender, beneficiary, arbiter, amount));
+
+          Ok(())
+      }
+
+      // releases an escrow to its beneficiary
+      // can be called by the payer or the arbiter
+      fn release_escrow(origin, escrow_id: u64) -> Result {
+          let sender = ensure_signed(origin)?;
+          let escrow = Self::escrow(escrow_id).ok_or("Escrow does not exist")?;
+          ensure!(sender == escrow.payer || sender == escrow.arbiter, "Only the payer or the arbiter can release an escrow");
+          Self::ensure_can_transfer(escrow.token_id, &escrow.payer, &escrow.beneficiary)?;
+
+          Self::_move(escrow.token_id, Self::escrow_account(escrow_id)?, escrow.beneficiary.clone(), escrow.amount)?;
+          Self::close_escrow(escrow_id, escrow.token_id);
+
+          Self::deposit_event(RawEvent::EscrowReleased(escrow_id, escrow.beneficiary, escrow.amount));
+
+          Ok(())
+      }
+
+      // refunds an escrow to its payer
+      // can be called by the beneficiary or the arbiter
+      fn refund_escrow(origin, escrow_id: u64) -> Result {
+          let sender = ensure_signed(origin)?;
+          let escrow = Self::escrow(escrow_id).ok_or("Escrow does not exist")?;
+          ensure!(sender == escrow.beneficiary || sender == escrow.arbiter, "Only the beneficiary or the arbiter can refund an escrow");
+
+          Self::_refund_escrow(escrow_id, escrow)
+      }
+
+      // flags or unflags a system account as quiet
+      // transfers between two quiet accounts are aggregated into one event per block
+      // transfers involving any other account are evented as usual
+      // can only be called by the force origin (governance)
+      fn set_quiet_account(origin, who: T::AccountId, quiet: bool) -> Result {
+          T::ForceOrigin::ensure_origin(origin)?;
+
+          if quiet {
+              <QuietAccounts<T>>::insert(who.clone(), true);
+          } else {
+              <QuietAccounts<T>>::remove(who.clone());
+          }
+
+          Self::deposit_event(RawEvent::QuietAccountSet(who, quiet));
+
+          Ok(())
+      }
+
+      // locks tokens of the sender for the receiver under the hash of a secret preimage
+      // the hash is keccak-256, so that the counterpart lock can be on an ethereum-like chain
+      // the receiver can claim the tokens with the preimage until the timeout
+      fn lock_htlc(origin, token_id: u32, to: T::AccountId, amount: T::TokenBalance, hash: H256, timeout: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(!amount.is_zero(), "Locked amount cannot be zero");
+          ensure!(timeout > <system::Module<T>>::block_number(), "Timeout must be in the future");
+          ensure!(!<Htlcs<T>>::exists(hash), "Hash is already locked");
+          Self::ensure_can_transfer(token_id, &sender, &to)?;
+
+          <Self as HoldableToken<_, _>>::hold(token_id, HTLC_HOLD, &sender, amount)?;
+          let htlc = Htlc {
+              token_id,
+              from: sender.clone(),
+              to: to.clone(),
+              amount,
+              timeout: timeout.clone(),
+          };
+          <Htlcs<T>>::insert(hash, htlc);
+          <OpenHolds<T>>::mutate(token_id, |count| *count = count.saturating_add(1));
+
+          Self::deposit_event(RawEvent::HtlcLocked(hash, token_id, sender, to, amount, timeout));
+
+          Ok(())
+      }
+
+      // claims the tokens of a hashed timelock for its receiver by revealing the preimage
+      // anyone can submit the preimage, the tokens always go to the receiver
+      fn claim_htlc(origin, preimage: [u8; 32]) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let hash = H256::from(runtime_io::keccak_256(&preimage));
+          let htlc = Self::htlc(hash).ok_or("No hashed timelock for this preimage")?;
+          ensure!(<system::Module<T>>::block_number() <= htlc.timeout, "Hashed timelock has timed out");
+          Self::ensure_can_transfer(htlc.token_id, &htlc.from, &htlc.to)?;
+
+          Self::transfer_held(htlc.token_id, HTLC_HOLD, &htlc.from, &htlc.to, htlc.amount)?;
+          <Htlcs<T>>::remove(hash);
+          Self::close_hold(htlc.token_id);
+
+          Self::deposit_event(RawEvent::HtlcClaimed(hash, preimage, htlc.to, htlc.amount));
+
+          Ok(())
+      }
+
+      // refunds the tokens of a timed out hashed timelock to its sender
+      // anyone can trigger the refund, the tokens always go back to the sender
+      fn refund_htlc(origin, hash: H256) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let htlc = Self::htlc(hash).ok_or("No hashed timelock for this hash")?;
+          ensure!(<system::Module<T>>::block_number() > htlc.timeout, "Hashed timelock has not timed out yet");
+
+          Self::_refund_htlc(hash, htlc);
+
+          Ok(())
+      }
+
+      // opens a crowdsale of a token for the native currency
+      // the cap is taken from the owner's balance and held until the sale is finalised
+      // the sale is successful if at least the soft cap is sold before the end
+      fn start_sale(origin, token_id: u32, price: T::Balance, cap: T::TokenBalance, soft_cap: T::TokenBalance, start: T::BlockNumber, end: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::_start_sale(sender, token_id, price, cap, soft_cap, start, end)
+      }
+
+      // opens a crowdsale like start_sale, whose orders may exceed the cap
+      // an oversubscribed launch fills the cap from a random position among the orders at finalisation,
+      // so buyers gain nothing by racing to order first
+      // each buyer places a single order, the payment for its unfilled part is refunded when it is claimed
+      fn start_fair_launch(origin, token_id: u32, price: T::Balance, cap: T::TokenBalance, soft_cap: T::TokenBalance, start: T::BlockNumber, end: T::BlockNumber) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::_start_sale(sender, token_id, price, cap, soft_cap, start, end)?;
+          <FairLaunches<T>>::insert(token_id, FairLaunch { ordered: Zero::zero(), draw: None });
+
+          Ok(())
+      }
+
+      // buys tokens in an open crowdsale
+      // the cost is moved to the sale account of the token and reserved there until the sale is finalised
+      // the buyer also pays the transfer fee
+      // the tokens can be claimed once the sale has ended successfully
+      fn purchase(origin, token_id: u32, amount: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let mut sale = Self::sale(token_id).ok_or("Token has no sale")?;
+          let now = <system::Module<T>>::block_number();
+          ensure!(now >= sale.start, "Sale has not started");
+          ensure!(now <= sale.end && !sale.finalised, "Sale has ended");
+          ensure!(!amount.is_zero(), "Purchase amount cannot be zero");
+          ensure!(sender != sale.issuer, "Issuer cannot buy in its own sale");
+          Self::ensure_can_transfer(token_id, &sale.issuer, &sender)?;
+          if let Some(launch) = Self::fair_launch(token_id) {
+              return Self::place_fair_order(token_id, sender, sale, launch, amount);
+          }
+          ensure!(sale.sold < sale.cap, "Sale is sold out");
+
+          let sold = math::checked_add(sale.sold, amount, "overflow in calculating sold tokens")?;
+          ensure!(sold <= sale.cap, "Purchase exceeds the sale cap");
+          let cost = Self::sale_cost(token_id, sale.price, amount)?;
+          let raised = sale.raised.checked_add(&cost).ok_or("overflow in calculating raised balance")?;
+          let (bought, paid) = Self::purchase_of((token_id, sender.clone()));
+          let bought = math::checked_add(bought, amount, "overflow in calculating bought tokens")?;
+          let paid = paid.checked_add(&cost).ok_or("overflow in calculating paid balance")?;
+          ensure!(<balances::Module<T>>::free_balance(&sender) >= cost, "Not enough balance to pay for the purchase");
+          let account = Self::sale_account(token_id)?;
+
+          <balances::Module<T>>::make_transfer(&sender, &account, cost)?;
+          <balances::Module<T>>::reserve(&account, cost)?;
+
+          if !<Purchases<T>>::exists((token_id, sender.clone())) {
+              sale.buyers = sale.buyers.saturating_add(1);
+          }
+          <Purchases<T>>::insert((token_id, sender.clone()), (bought, paid));
+          sale.sold = sold;
+          sale.raised = raised;
+          let sold_out = sale.sold == sale.cap;
+          <Sales<T>>::insert(token_id, sale);
+
+          Self::deposit_event(RawEvent::Purchased(token_id, sender, amount, cost));
+          if sold_out {
+              Self::deposit_event(RawEvent::SoldOut(token_id));
+          }
+
+          Ok(())
+      }
+
+      // finalises a crowdsale once it has ended or sold out
+      // a successful sale pays the proceeds to the issuer and returns the unsold tokens
+      // a failed sale returns all the tokens, the buyers claim their refunds
+      // a fair launch only ends at its end block, an oversubscribed one draws the position its fills start from,
+      // using the random seed of the finalising block, and its proceeds are paid as the orders are claimed
+      // anyone can finalise a sale
+      fn finalise_sale(origin, token_id: u32) -> Result {
+          let _sender = ensure_signed(origin)?;
+          let mut sale = Self::sale(token_id).ok_or("Token has no sale")?;
+          ensure!(!sale.finalised, "Sale is already finalised");
+          let fair_launch = Self::fair_launch(token_id);
+          ensure!(<system::Module<T>>::block_number() > sale.end || (sale.sold == sale.cap && fair_launch.is_none()), "Sale has not ended");
+
+          let successful = sale.sold >= sale.soft_cap;
+          let draw = match fair_launch {
+              Some(ref launch) if successful && launch.ordered > sale.cap => Some(Self::draw_fair_launch(token_id, launch.ordered)?),
+              _ => None,
+          };
+          let proceeds = if successful && fair_launch.is_none() { sale.raised } else { Zero::zero() };
+          let returned = if successful { sale.cap - sale.sold } else { sale.cap };
+          if !proceeds.is_zero() {
+              ensure!(!<balances::Module<T>>::total_balance(&sale.issuer).is_zero(), "Issuer account does not exist");
+              let account = Self::sale_account(token_id)?;
+              let _ = <balances::Module<T>>::repatriate_reserved(&account, &sale.issuer, proceeds)?;
+          }
+          let _ = <Self as HoldableToken<_, _>>::release(token_id, SALE_HOLD, &sale.issuer, returned);
+          if let (Some(mut launch), Some(draw)) = (fair_launch, draw) {
+              launch.draw = Some(draw);
+              <FairLaunches<T>>::insert(token_id, launch);
+              Self::deposit_event(RawEvent::FairLaunchDrawn(token_id, draw));
+          }
+
+          sale.finalised = true;
+          Self::deposit_event(RawEvent::SaleFinalised(token_id, successful, sale.sold, proceeds));
+          Self::store_or_close_sale(token_id, sale);
+
+          Ok(())
+      }
+
+      // settles the purchases of the sender in a finalised crowdsale
+      // the bought tokens are delivered if the sale was successful, the payment is refunded otherwise
+      // an order in a fair launch gets its filled tokens, and the payment for the rest back
+      fn claim_sale(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          let mut sale = Self::sale(token_id).ok_or("Token has no sale")?;
+          ensure!(sale.finalised, "Sale is not finalised");
+          ensure!(<Purchases<T>>::exists((token_id, sender.clone())), "Account has no purchases in this sale");
+          let (bought, paid) = Self::purchase_of((token_id, sender.clone()));
+
+          if sale.sold >= sale.soft_cap {
+              match Self::fair_order((token_id, sender.clone())) {
+                  Some((position, amount)) => Self::settle_fair_order(token_id, &sale, &sender, position, amount, paid)?,
+                  None => {
+                      Self::transfer_held(token_id, SALE_HOLD, &sale.issuer, &sender, bought)?;
+                      Self::deposit_event(RawEvent::SaleTokensClaimed(token_id, sender.clone(), bought));
+                  },
+              }
+          } else {
+              let account = Self::sale_account(token_id)?;
+              let missing = <balances::Module<T>>::repatriate_reserved(&account, &sender, paid)?;
+              Self::deposit_event(RawEvent::SaleRefunded(token_id, sender.clone(), paid - missing));
+          }
+
+          <Purchases<T>>::remove((token_id, sender.clone()));
+          <FairOrders<T>>::remove((token_id, sender));
+          sale.buyers = sale.buyers.saturating_sub(1);
+          Self::store_or_close_sale(token_id, sale);
+
+          Ok(())
+      }
+
+      // issues a token along a bonding curve against the native currency
+      // the reserve that backs the issued tokens is held on the curve account of the token
+      // the sender becomes the custodian, which receives what is left of the reserve when the curve is disabled
+      fn enable_bonding_curve(origin, token_id: u32, curve: BondingCurve<T::TokenBalance, T::Balance>) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          ensure!(!<BondingCurves<T>>::exists(token_id), "Token already has a bonding curve");
+          ensure!(Self::token_details(token_id).mintable, "Token is not mintable");
+          match curve {
+              BondingCurve::Linear(_, _, ref denominator) => ensure!(!denominator.is_zero(), "Slope denominator cannot be zero"),
+              BondingCurve::Exponential(_, _, ref step) => ensure!(!step.is_zero(), "Price step cannot be zero"),
+          }
+
+          let issuance = CurveIssuance {
+              curve,
+              custodian: sender.clone(),
+              supply: Zero::zero(),
+              reserve: Zero::zero(),
+          };
+          <BondingCurves<T>>::insert(token_id, issuance);
+
+          Self::deposit_event(RawEvent::BondingCurveEnabled(token_id, sender));
+
+          Ok(())
+      }
+
+      // stops issuing a token along its bonding curve
+      // only possible once all the tokens issued through the curve have been sold back
+      // the rounding left in the reserve goes to the custodian
+      fn disable_bonding_curve(origin, token_id: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+          let issuance = Self::bonding_curve(token_id).ok_or("Token has no bonding curve")?;
+          ensure!(issuance.supply.is_zero(), "Tokens issued through the curve are outstanding");
+
+          if !issuance.reserve.is_zero() {
+              let account = Self::curve_account(token_id)?;
+              let _ = <balances::Module<T>>::repatriate_reserved(&account, &issuance.custodian, issuance.reserve)?;
+          }
+          <BondingCurves<T>>::remove(token_id);
+
+          Self::deposit_event(RawEvent::BondingCurveDisabled(token_id));
+
+          Ok(())
+      }
+
+      // mints tokens from the bonding curve of a token, paying the curve price in the native currency
+      // the cost is moved to the curve account and reserved there, the buyer also pays the transfer fee
+      // fails if the cost is above max_cost, so that the sender is protected from price moves
+      fn buy(origin, token_id: u32, amount: T::TokenBalance, max_cost: T::Balance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let mut issuance = Self::bonding_curve(token_id).ok_or("Token has no bonding curve")?;
+          ensure!(!amount.is_zero(), "Amount cannot be zero");
+          ensure!(sender != issuance.custodian, "Custodian cannot trade on its own curve");
+
+          let supply = math::checked_add(issuance.supply, amount, "overflow in calculating curve supply")?;
+          let cost = Self::curve_cost(token_id, &issuance.curve, issuance.supply, amount, true)?;
+          ensure!(cost <= max_cost, "Cost exceeds the maximum");
+          let reserve = issuance.reserve.checked_add(&cost).ok_or("overflow in calculating curve reserve")?;
+          ensure!(<balances::Module<T>>::free_balance(&sender) >= cost, "Not enough balance to pay for the tokens");
+          let account = Self::curve_account(token_id)?;
+          Self::ensure_can_mint(token_id, &sender, amount)?;
+
+          // the reserve is taken before minting, so the tokens are only minted once they are paid for
+          <balances::Module<T>>::make_transfer(&sender, &account, cost)?;
+          <balances::Module<T>>::reserve(&account, cost)?;
+          Self::_mint(token_id, sender.clone(), amount)?;
+
+          issuance.supply = supply;
+          issuance.reserve = reserve;
+          <BondingCurves<T>>::insert(token_id, issuance);
+
+          Self::deposit_event(RawEvent::CurveBought(token_id, sender, amount, cost));
+
+          Ok(())
+      }
+
+      // burns tokens back into the bonding curve of a token, receiving the curve price from the reserve
+      // fails if the payout is below min_payout, so that the sender is protected from price moves
+      fn sell(origin, token_id: u32, amount: T::TokenBalance, min_payout: T::Balance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let mut issuance = Self::bonding_curve(token_id).ok_or("Token has no bonding curve")?;
+          ensure!(!amount.is_zero(), "Amount cannot be zero");
+          ensure!(sender != issuance.custodian, "Custodian cannot trade on its own curve");
+          ensure!(amount <= issuance.supply, "Amount exceeds the curve supply");
+
+          let supply = issuance.supply - amount;
+          let payout = Self::curve_cost(token_id, &issuance.curve, supply, amount, false)?;
+          ensure!(payout >= min_payout, "Payout is below the minimum");
+          ensure!(payout <= issuance.reserve, "Curve reserve cannot cover the payout");
+          let account = Self::curve_account(token_id)?;
+          ensure!(<balances::Module<T>>::reserved_balance(&account) >= payout, "Curve reserve is no longer held");
+          ensure!(!<balances::Module<T>>::total_balance(&sender).is_zero(), "Seller account does not exist");
+          Self::ensure_can_burn(token_id, &sender, amount)?;
+
+          Self::_burn(token_id, sender.clone(), amount)?;
+          let _ = <balances::Module<T>>::repatriate_reserved(&account, &sender, payout)?;
+
+          issuance.supply = supply;
+          issuance.reserve = issuance.reserve - payout;
+          <BondingCurves<T>>::insert(token_id, issuance);
+
+          Self::deposit_event(RawEvent::CurveSold(token_id, sender, amount, payout));
+
+          Ok(())
+      }
+
+      // proposes correcting the name and ticker of a token, e.g. one impersonating another token
+      // the moderation bond is reserved from the sender until the proposal is resolved
+      fn propose_moderation(origin, token_id: u32, name: Vec<u8>, ticker: Vec<u8>) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+          ensure!(!<ModerationProposals<T>>::exists(token_id), "Token already has a moderation proposal");
+          Self::ensure_metadata_length(&name, &ticker)?;
+
+          let bond = Self::moderation_bond();
+          <balances::Module<T>>::reserve(&sender, bond).map_err(|_| "Not enough balance to pay the moderation bond")?;
+          let proposal = ModerationProposal {
+              proposer: sender.clone(),
+              name: name.clone(),
+              ticker: ticker.clone(),
+              bond,
+          };
+          <ModerationProposals<T>>::insert(token_id, proposal);
+
+          Self::deposit_event(RawEvent::ModerationProposed(token_id, sender, name, ticker));
+
+          Ok(())
+      }
+
+      // accepts a metadata correction, even if the metadata of the token is frozen
+      // the issuer deposit of the token is slashed and the proposer's bond is returned
+      // can only be called by the moderation origin
+      fn accept_moderation(origin, token_id: u32) -> Result {
+          T::ModerationOrigin::ensure_origin(origin)?;
+          let proposal = Self::moderation_proposal(token_id).ok_or("Token has no moderation proposal")?;
+
+          Self::write_name_and_ticker(token_id, proposal.name, proposal.ticker)?;
+          <ModerationProposals<T>>::remove(token_id);
+          let _ = <balances::Module<T>>::unreserve(&proposal.proposer, proposal.bond);
+          let (issuer, slashed) = match <IssuerDeposits<T>>::take(token_id) {
+              Some((issuer, deposit)) => {
+                  <balances::Module<T>>::slash_reserved(&issuer, deposit);
+                  (Some(issuer), deposit)
+              },
+              None => (None, Zero::zero()),
+          };
+
+          Self::deposit_event(RawEvent::ModerationAccepted(token_id, issuer, slashed));
+
+          Ok(())
+      }
+
+      // rejects a metadata correction and slashes the proposer's bond
+      // can only be called by the moderation origin
+      fn reject_moderation(origin, token_id: u32) -> Result {
+          T::ModerationOrigin::ensure_origin(origin)?;
+          let proposal = Self::moderation_proposal(token_id).ok_or("Token has no moderation proposal")?;
+
+          <ModerationProposals<T>>::remove(token_id);
+          <balances::Module<T>>::slash_reserved(&proposal.proposer, proposal.bond);
+
+          Self::deposit_event(RawEvent::ModerationRejected(token_id, proposal.proposer, proposal.bond));
+
+          Ok(())
+      }
+
+      // creates a constant product liquidity pool between two tokens
+      // the lp token of the pool is created as a system token, so the creation fee is charged
+      // the lp token has the larger of the decimals of the two tokens
+      fn create_pool(origin, token_a: u32, token_b: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(token_a != token_b, "Pool tokens must differ");
+          ensure!(<Tokens<T>>::exists(token_a) && <Tokens<T>>::exists(token_b), "Token does not exist");
+          let (token_a, token_b) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+          ensure!(!<PoolOf<T>>::exists((token_a, token_b)), "Tokens already have a pool");
+
+          let pool_id = Self::pool_count();
+          let next_pool_id = pool_id.checked_add(1).ok_or("overflow in calculating next pool id")?;
+          let account = Self::pool_account(pool_id)?;
+          let (name, ticker) = Self::lp_token_metadata(pool_id);
+          Self::ensure_can_init(&name, &ticker, Zero::zero(), None)?;
+          Self::charge_creation_fee(&sender, 1)?;
+          let lp_token = Self::init_system_token(account.clone(), name, ticker, Zero::zero(), None, true, false)?;
+          let decimals = Self::metadata(token_a).decimals.max(Self::metadata(token_b).decimals);
+          <Metadata<T>>::mutate(lp_token, |metadata| metadata.decimals = decimals);
+          Self::record_issuer_deposit(lp_token, &sender);
+
+          <PoolCount<T>>::put(next_pool_id);
+          <PoolOf<T>>::insert((token_a, token_b), pool_id);
+          <PooledTokens<T>>::insert(token_a, true);
+          <PooledTokens<T>>::insert(token_b, true);
+          let pool = Pool {
+              token_a,
+              token_b,
+              account: account.clone(),
+              lp_token,
+          };
+          <Pools<T>>::insert(pool_id, pool);
+
+          Self::deposit_event(RawEvent::PoolCreated(pool_id, token_a, token_b, account, lp_token));
+
+          Ok(())
+      }
+
+      // adds liquidity to a pool, taking up to the given amounts in the current ratio of the reserves
+      // the first provider sets the ratio and receives the square root of the product of the amounts,
+      // less the minimum liquidity which is minted to the pool account for good
+      // the amounts are scaled to the decimals of the lp token first, so that neither token is undervalued
+      // fails if fewer than min_liquidity lp tokens would be minted
+      fn add_liquidity(origin, pool_id: u32, max_a: T::TokenBalance, max_b: T::TokenBalance, min_liquidity: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let pool = Self::pool(pool_id).ok_or("Pool does not exist")?;
+          let reserve_a = Self::balance_of(pool.token_a, &pool.account);
+          let reserve_b = Self::balance_of(pool.token_b, &pool.account);
+          let lp_supply = Self::token_details(pool.lp_token).total_supply;
+
+          let first_deposit = lp_supply.is_zero() || reserve_a.is_zero() || reserve_b.is_zero();
+          let locked = if lp_supply.is_zero() { T::TokenBalance::sa(MINIMUM_LIQUIDITY) } else { Zero::zero() };
+          let (amount_a, amount_b, liquidity) = if first_deposit {
+              let decimals = Self::metadata(pool.lp_token).decimals;
+              let scaled_a = math::scale_decimals(max_a, Self::metadata(pool.token_a).decimals, decimals)?;
+              let scaled_b = math::scale_decimals(max_b, Self::metadata(pool.token_b).decimals, decimals)?;
+              let liquidity = math::sqrt_product(scaled_a, scaled_b)?;
+              ensure!(liquidity > locked, "Initial liquidity is too low");
+              (max_a, max_b, liquidity - locked)
+          } else {
+              let optimal_b = math::mul_div(max_a, reserve_b, reserve_a)?;
+              let (amount_a, amount_b) = if optimal_b <= max_b {
+                  (max_a, optimal_b)
+              } else {
+                  (math::mul_div(max_b, reserve_a, reserve_b)?, max_b)
+              };
+              (amount_a, amount_b, math::mul_div(amount_a, lp_supply, reserve_a)?)
+          };
+          ensure!(!amount_a.is_zero() && !amount_b.is_zero(), "Liquidity amounts cannot be zero");
+          ensure!(!liquidity.is_zero() && liquidity >= min_liquidity, "Liquidity is below the minimum");
+
+          let leg_a = Self::plan_pool_leg(pool.token_a, &sender, &pool.account, amount_a)?;
+          let leg_b = Self::plan_pool_leg(pool.token_b, &sender, &pool.account, amount_b)?;
+          Self::ensure_can_mint(pool.lp_token, &sender, liquidity)?;
+          if !locked.is_zero() {
+              Self::ensure_can_mint(pool.lp_token, &pool.account, locked)?;
+          }
+
+          leg_a.commit();
+          leg_b.commit();
+          if !locked.is_zero() {
+              Self::_mint(pool.lp_token, pool.account.clone(), locked)?;
+          }
+          Self::_mint(pool.lp_token, sender.clone(), liquidity)?;
+          Self::store_receipt(&(pool_id, sender.clone(), amount_a, amount_b, liquidity));
+
+          Self::deposit_event(RawEvent::LiquidityAdded(pool_id, sender, amount_a, amount_b, liquidity));
+
+          Ok(())
+      }
+
+      // burns lp tokens of a pool for the share of the reserves they represent
+      // fails if less than min_a or min_b would be paid out
+      fn remove_liquidity(origin, pool_id: u32, liquidity: T::TokenBalance, min_a: T::TokenBalance, min_b: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let pool = Self::pool(pool_id).ok_or("Pool does not exist")?;
+          ensure!(!liquidity.is_zero(), "Liquidity cannot be zero");
+          let lp_supply = Self::token_details(pool.lp_token).total_supply;
+          ensure!(liquidity <= lp_supply, "Liquidity exceeds the lp supply");
+
+          let amount_a = math::mul_div(liquidity, Self::balance_of(pool.token_a, &pool.account), lp_supply)?;
+          let amount_b = math::mul_div(liquidity, Self::balance_of(pool.token_b, &pool.account), lp_supply)?;
+          ensure!(amount_a >= min_a && amount_b >= min_b, "Payout is below the minimum");
+
+          Self::ensure_can_burn(pool.lp_token, &sender, liquidity)?;
+          let leg_a = Self::plan_pool_leg(pool.token_a, &pool.account, &sender, amount_a)?;
+          let leg_b = Self::plan_pool_leg(pool.token_b, &pool.account, &sender, amount_b)?;
+
+          Self::_burn(pool.lp_token, sender.clone(), liquidity)?;
+          leg_a.commit();
+          leg_b.commit();
+          Self::store_receipt(&(pool_id, sender.clone(), amount_a, amount_b, liquidity));
+
+          Self::deposit_event(RawEvent::LiquidityRemoved(pool_id, sender, amount_a, amount_b, liquidity));
+
+          Ok(())
+      }
+
+      // swaps an exact amount of one pool token for the other, keeping the product of the reserves
+      // the swap fee is taken from the input and stays in the pool
+      // fails if less than min_out would be paid out
+      fn swap_exact_in(origin, pool_id: u32, token_in: u32, amount_in: T::TokenBalance, min_out: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          let pool = Self::pool(pool_id).ok_or("Pool does not exist")?;
+          let token_out = if token_in == pool.token_a {
+              pool.token_b
+          } else {
+              ensure!(token_in == pool.token_b, "Token is not in the pool");
+              pool.token_a
+          };
+          ensure!(!amount_in.is_zero(), "Swap amount cannot be zero");
+
+          let amount_out = Self::pool_swap_out(&pool, token_in, token_out, amount_in)?;
+          ensure!(!amount_out.is_zero() && amount_out >= min_out, "Payout is below the minimum");
+
+          let leg_in = Self::plan_pool_leg(token_in, &sender, &pool.account, amount_in)?;
+          let leg_out = Self::plan_pool_leg(token_out, &pool.account, &sender, amount_out)?;
+          let open = Self::pool_price(&pool);
+
+          leg_in.commit();
+          leg_out.commit();
+          let volume = if token_in == pool.token_a { amount_in } else { amount_out };
+          Self::record_candle(pool_id, open, Self::pool_price(&pool), volume);
+          Self::store_receipt(&(pool_id, sender.clone(), token_in, amount_in, token_out, amount_out));
+
+          Self::deposit_event(RawEvent::PoolSwapped(pool_id, sender, token_in, amount_in, token_out, amount_out));
+
+          Ok(())
+      }
+
+      // sets the balance of a token at or below which it counts as dust for sweep_dust
+      // zero, the default, leaves no balance to be swept
+      // only the owner of the token can set it
+      fn set_dust_threshold(origin, token_id: u32, threshold: T::TokenBalance) -> Result {
+          let sender = ensure_signed(origin)?;
+          Self::ensure_owner(token_id, &sender)?;
+
+          <DustThreshold<T>>::insert(token_id, threshold);
+
+          Self::deposit_event(RawEvent::DustThresholdChanged(token_id, threshold));
+
+          Ok(())
+      }
+
+      // swaps the whole dust balances of the sender in the given tokens for the target token in one call
+      // each token is swapped through its pool with the target token, freeing the balance entry of the sender
+      // the swaps have no minimum payout, a dust balance may well be worth nothing in the target token
+      // every swap is planned before any is written, so either all tokens are swept or none
+      fn sweep_dust(origin, token_ids: Vec<u32>, target_token: u32) -> Result {
+          let sender = ensure_signed(origin)?;
+          ensure!(token_ids.len() <= Self::max_batch_size() as usize, "Batch exceeds the max batch size");
+
+          let mut legs_in = Vec::with_capacity(token_ids.len());
+          let mut swaps = Vec::with_capacity(token_ids.len());
+          let mut leg_out = Ledger::new(target_token);
+          let mut total_out: T::TokenBalance = Zero::zero();
+          for (index, token_id) in token_ids.iter().enumerate() {
+              let token_id = *token_id;
+              ensure!(token_id != target_token, "Cannot sweep the target token");
+              ensure!(!token_ids[..index].contains(&token_id), "Token is swept twice");
+              let balance = Self::balance_of(token_id, &sender);
+              ensure!(!balance.is_zero(), "Account does not own this token");
+              ensure!(balance <= Self::dust_threshold(token_id), "Balance is above the dust threshold");
+
+              let pair = if token_id < target_token { (token_id, target_token) } else { (target_token, token_id) };
+              let pool_id = Self::pool_of(pair).ok_or("Tokens have no pool")?;
+              let pool = Self::pool(pool_id).ok_or("Pool does not exist")?;
+              let amount_out = Self::pool_swap_out(&pool, token_id, target_token, balance)?;
+
+              legs_in.push(Self::plan_pool_leg(token_id, &sender, &pool.account, balance)?);
+              if !amount_out.is_zero() {
+                  leg_out.move_restricted(&pool.account, &sender, amount_out)?;
+              }
+              total_out = math::checked_add(total_out, amount_out, "overflow in calculating swept amount")?;
+              swaps.push((pool_id, pool.clone(), Self::pool_price(&pool), token_id, balance, amount_out));
+          }
+
+          for leg in legs_in {
+              leg.commit();
+          }
+          leg_out.commit();
+          for (pool_id, pool, open, token_id, balance, amount_out) in swaps {
+              let volume = if token_id == pool.token_a { balance } else { amount_out };
+              Self::record_candle(pool_id, open, Self::pool_price(&pool), volume);
+              Self::deposit_event(RawEvent::PoolSwapped(pool_id, sender.clone(), token_id, balance, target_token, amount_out));
+          }
+          Self::store_receipt(&(sender.clone(), target_token, token_ids.clone(), total_out));
+
+          Self::deposit_event(RawEvent::DustSwept(sender, target_token, token_ids.len() as u32, total_out));
+
+          Ok(())
+      }
+  }
+}
+
+// storage for this module
+decl_storage! {
+  trait Store for Module<T: Trait> as Erc20 {
+      // limits of the module, stored so that they show up in the metadata and can differ per chain
+      // the defaults apply to chains whose genesis did not set them, e.g. after a runtime upgrade
+      // max number of legs in a single batch call
+      MaxBatchSize get(max_batch_size) config(): u32 = 256;
+      // max length in bytes of a token name
+      MaxNameLength get(max_name_length) config(): u32 = 64;
+      // max length in bytes of a token ticker
+      MaxTickerLength get(max_ticker_length) config(): u32 = 32;
+      // max length in bytes of a token uri
+      MaxUriLength get(max_uri_length) config(): u32 = 256;
+      // max length in bytes of a transfer memo
+      MaxMemoLength get(max_memo_length) config(): u32 = 128;
+      // max number of guardians an account can register for recovery
+      MaxGuardians get(max_guardians) config(): u32 = 16;
+      // max number of queued transfer intents of a token
+      MaxIntents get(max_intents) config(): u32 = 256;
+      // max number of queued transfer intents of a token from one sender
+      // keeps a single account from filling the queue of a token for everyone else
+      MaxSenderIntents get(max_sender_intents) config(): u32 = 16;
+      // max length in bytes of the encoded asset id of another registry
+      MaxExternalIdLength get(max_external_id_length) config(): u32 = 32;
+      // max number of entries returned in one page of holders or incoming allowances
+      MaxPageSize get(max_page_size) config(): u32 = 1000;
+      // max number of different tokens an account can hold, unbounded unless set
+      // an account can free a slot with discard_balance
+      MaxAccountTokens get(max_account_tokens) config(): u32 = u32::max_value();
+      // ticker prefixes reserved for tokens created by other runtime modules, e.g. lp or bridged tokens
+      ReservedTickerPrefixes get(reserved_ticker_prefixes) config(): Vec<Vec<u8>>;
+      // token id nonce for storing the next token id available for token initialization
+      // inspired by the AssetId in the SRML assets module
+      TokenId get(token_id): u32;
+      // details of the token corresponding to a token id
+      Tokens get(token_details): map u32 => Erc20Token<T::TokenBalance>;
+      // name, ticker and off-chain info of the token corresponding to a token id
+      Metadata get(metadata): map u32 => TokenMetadata;
+      // owner of the token corresponding to a token id
+      Owners get(owner_of): map u32 => Option<T::AccountId>;
+      // tokens owned by an account, so that recovery does not need to scan every token
+      // tokens created before this index existed are not in it
+      OwnedTokens get(owned_tokens): map T::AccountId => Vec<u32>;
+      // legacy balances mapping keyed by the (token, account) tuple
+      // balances now live in the `Balances` double map, entries left here are moved over on first access
+      BalanceOf: map (u32, T::AccountId) => T::TokenBalance;
+      // legacy allowance mapping keyed by the (token, owner, spender) tuple
+      // allowances now live in the `Allowances` double map, entries left here are moved over on first access
+      Allowance: map (u32, T::AccountId, T::AccountId) => T::TokenBalance;
+      // spenders an owner has approved for a token, in order of first approval
+      ApprovedSpenders get(approved_spenders): map (u32, T::AccountId) => Vec<T::AccountId>;
+      // number of allowances given to a spender
+      IncomingAllowanceCount get(incoming_allowance_count): map T::AccountId => u32;
+      // token and owner of the allowances given to a spender by position, positions run from zero up to the count
+      // kept as separate entries, so that approvals anyone can give do not grow a single value
+      IncomingAllowanceAt get(incoming_allowance_at): map (T::AccountId, u32) => (u32, T::AccountId);
+      // position of an allowance among the allowances given to the spender, keyed like the allowance
+      IncomingAllowanceIndex: map (u32, T::AccountId, T::AccountId) => Option<u32>;
+      // mint offer id nonce for storing the next mint offer id available
+      MintOfferId get(mint_offer_id): u64;
+      // pending mint offers waiting for acceptance by the recipient
+      MintOffers get(mint_offer): map u64 => Option<MintOffer<T::AccountId, T::TokenBalance, T::BlockNumber>>;
+      // number of pending mint offers of a token
+      MintOfferCount get(mint_offer_count): map u32 => u32;
+      // guardians registered by an account for recovery
+      Guardians get(guardians): map T::AccountId => Option<GuardianSet<T::AccountId, T::BlockNumber>>;
+      // active recoveries, keyed by the lost account
+      Recoveries get(recovery): map T::AccountId => Option<Recovery<T::AccountId, T::BlockNumber>>;
+      // tokens whose transfers are paused by their owner
+      Paused get(is_paused): map u32 => bool;
+      // tokens denied chain-wide by governance
+      DeniedTokens get(is_denied): map u32 => bool;
+      // accounts frozen by the token owner, keyed by token and account
+      Frozen get(is_frozen): map (u32, T::AccountId) => bool;
+      // token id corresponding to an asset id of another registry
+      TokenOfExternalAsset get(token_of_external_asset): map (u8, Vec<u8>) => Option<u32>;
+      // asset id of another registry corresponding to a token id
+      ExternalAssetOfToken get(external_asset_of_token): map (u32, u8) => Option<Vec<u8>>;
+      // accounts blacklisted by the token owner, keyed by token and account
+      Blacklist get(is_blacklisted): map (u32, T::AccountId) => bool;
+      // accounts allowed to receive whitelisted tokens, keyed by token and account
+      Whitelist get(is_whitelisted): map (u32, T::AccountId) => bool;
+      // block after which the privileged calls of a token are permanently disabled
+      AdminSunset get(admin_sunset): map u32 => Option<T::BlockNumber>;
+      // token id registered for a ticker
+      // keyed by the normalized (uppercase ascii) ticker
+      TickerRegistry get(ticker_registry): map Vec<u8> => Option<u32>;
+      // queued transfer intents of a token waiting for settlement
+      // from, to, value
+      Intents get(intents): map u32 => Vec<(T::AccountId, T::AccountId, T::TokenBalance)>;
+      // governed migration programs, keyed by the old token
+      SwapPrograms get(swap_program): map u32 => Option<SwapProgram<T::TokenBalance, T::BlockNumber>>;
+      // block number at which an allowance expires, keyed like the allowance
+      // allowances without an entry never expire
+      AllowanceExpiry get(allowance_expiry): map (u32, T::AccountId, T::AccountId) => Option<T::BlockNumber>;
+      // nonce of an account for off-chain signed messages
+      // incremented by every accepted signature to prevent replays
+      Nonces get(nonce_of): map T::AccountId => u64;
+      // minimum balance an account can hold of a token, zero if not set
+      MinBalance get(min_balance): map u32 => T::TokenBalance;
+      // balance of a token at or below which it can be swept with sweep_dust, zero if not set
+      DustThreshold get(dust_threshold): map u32 => T::TokenBalance;
+      // number of accounts holding a non zero balance of a token
+      HolderCount get(holder_count): map u32 => u32;
+      // holders of a token by position, positions run from zero up to the holder count
+      HolderAt get(holder_at): map (u32, u32) => T::AccountId;
+      // position of a holder of a token
+      // none for balances written before the holder index existed, they are indexed when next touched
+      HolderIndex: map (u32, T::AccountId) => Option<u32>;
+      // tokens an account holds a non zero balance of, bounded by the max account tokens
+      AccountTokens get(account_tokens): map T::AccountId => Vec<u32>;
+      // sub-allowances delegated by spenders, keyed by token, owner, spender and sub-spender
+      SubAllowances get(sub_allowance): map (u32, T::AccountId, T::AccountId, T::AccountId) => SubAllowance<T::TokenBalance>;
+      // sub-spenders a spender delegated part of its allowance from an owner to, keyed by token, owner and spender
+      // bounded by the max batch size, so that revoking the allowance can remove every sub-allowance
+      SubSpenders get(sub_spenders): map (u32, T::AccountId, T::AccountId) => Vec<T::AccountId>;
+      // revocation counter of an approval, keyed like the allowance
+      // sub-allowances made under an earlier epoch are no longer valid
+      AllowanceEpoch get(allowance_epoch): map (u32, T::AccountId, T::AccountId) => u32;
+      // tokens with transfers in the current block, in order of first transfer
+      TransferTokens: Vec<u32>;
+      // encoded transfers of a token in the current block, cleared when the block is finalised
+      TransferLeaves get(transfer_leaves): map u32 => Vec<Vec<u8>>;
+      // merkle root of the transfers of a token in a block
+      TransferRoots get(transfer_root): map (u32, T::BlockNumber) => Option<T::Hash>;
+      // blocks between scheduling and applying a logic module change
+      LogicTimelock get(logic_timelock) config(): T::BlockNumber;
+      // blocks between requesting to lift a lockdown and lifting it
+      LockdownDelay get(lockdown_delay) config(): T::BlockNumber;
+      // fee in the native currency for creating a token when creations are at the target
+      CreationBaseFee get(creation_base_fee) config(): T::Balance;
+      // token creations per block above which the creation fee rises
+      CreationTarget get(creation_target) config(): u32;
+      // native balance reserved from the creator of every token, slashed if moderation corrects its metadata
+      IssuerDeposit get(issuer_deposit) config(): T::Balance;
+      // native balance reserved from the proposer of a metadata correction, slashed if it is rejected
+      ModerationBond get(moderation_bond) config(): T::Balance;
+      // fee charged on the input of every pool swap, in parts per million, left in the pool for liquidity providers
+      SwapFee get(swap_fee) config(): u32;
+      // blocks after the dissent window of a buyout during which it can be executed
+      BuyoutExecutionPeriod get(buyout_execution_period) config(): T::BlockNumber;
+      // length in blocks of the period of a pool price candle
+      CandlePeriod get(candle_period) config(): u32 = 100;
+      // number of price candles kept per pool, the oldest candle is overwritten by a new period
+      MaxCandles get(max_candles) config(): u32 = 96;
+      // current token creation fee, never below the base fee
+      // moves by up to an eighth per block towards the creation volume, like eip-1559 base fees
+      CreationFee get(creation_fee_value): T::Balance;
+      // token creations in the current block
+      CreationsInBlock: u32;
+      // logic module a token is bound to for each kind
+      TokenLogic get(token_logic): map (u32, LogicKind) => Option<u32>;
+      // scheduled logic module change and the block from which it can be applied
+      PendingLogic get(pending_logic): map (u32, LogicKind) => Option<(Option<u32>, T::BlockNumber)>;
+      // balance of a token below which an account is notified
+      LowBalanceThreshold get(low_balance_threshold): map (u32, T::AccountId) => Option<T::TokenBalance>;
+      // number of balance snapshots taken of a token
+      SnapshotCount get(snapshot_count): map u32 => u32;
+      // block and child trie root of a balance snapshot of a token
+      Snapshots get(snapshot): map (u32, u32) => Option<(T::BlockNumber, Vec<u8>)>;
+      // snapshot of a token being written and the position of the next holder to write
+      SnapshotCursor get(snapshot_cursor): map u32 => Option<(u32, u32)>;
+      // number of approvals given for a token
+      ApprovalCount get(approval_count): map u32 => u32;
+      // whether a token is being destroyed
+      Destroying get(is_destroying): map u32 => bool;
+      // account that can lock down all the token balances of an account
+      LockdownGuardian get(lockdown_guardian): map T::AccountId => Option<T::AccountId>;
+      // whether all the token balances of an account are locked down
+      LockedDown get(is_locked_down): map T::AccountId => bool;
+      // block from which a locked down account can lift its lockdown
+      UnlockAt get(unlock_at): map T::AccountId => Option<T::BlockNumber>;
+      // whether the name, ticker, uri and metadata hash of a token can no longer be changed
+      FrozenMetadata get(is_metadata_frozen): map u32 => bool;
+      // account the owner of a token has delegated the uri and metadata hash to
+      MetadataManagers get(metadata_manager): map u32 => Option<T::AccountId>;
+      // unclaimed balances of migrated tokens held by ethereum addresses
+      EthereumClaims get(ethereum_claim): map (u32, EthereumAddress) => Option<T::TokenBalance>;
+      // number of ethereum addresses with an unclaimed balance of a migrated token
+      UnclaimedCount get(unclaimed_count): map u32 => u32;
+      // whether the transfer fees of a token are paid to the treasury
+      FeesToTreasury get(fees_to_treasury): map u32 => bool;
+      // parts per million of every transfer of a token that are burned
+      TransferBurnRate get(transfer_burn_rate): map u32 => u32;
+      // rounding of the fee and tax charges of a token, none for the default rounding of each charge
+      RoundingPolicy get(rounding_policy): map u32 => Option<Rounding>;
+      // credit line of a borrower for a token
+      CreditLines get(credit_line): map (u32, T::AccountId) => Option<CreditLine<T::AccountId, T::TokenBalance>>;
+      // receipt id nonce for storing the next receipt id available
+      ReceiptId get(receipt_id): u64;
+      // hash of what a multi-leg operation executed
+      Receipts get(receipt): map u64 => Option<T::Hash>;
+      // locks on the balance of an account for a token, by lock id
+      Locks get(locks): map (u32, T::AccountId) => Vec<(LockIdentifier, T::TokenBalance)>;
+      // reserved balance of an account for a token, held by other modules and not transferable
+      ReservedBalanceOf get(reserved_balance_of): map (u32, T::AccountId) => T::TokenBalance;
+      // sum of the reserved balances of a token
+      ReservedSupply get(reserved_supply): map u32 => T::TokenBalance;
+      // named holds on the balance of an account for a token, by reason
+      Holds get(holds): map (u32, T::AccountId) => Vec<(HoldReason, T::TokenBalance)>;
+      // forced buyout of the minority holders of a token
+      Buyouts get(buyout): map u32 => Option<Buyout<T::TokenBalance, T::BlockNumber>>;
+      // number of buyouts proposed for a token
+      BuyoutRounds get(buyout_rounds): map u32 => u32;
+      // whether a holder dissented with a buyout of a token, keyed by token, buyout round and holder
+      Dissents get(has_dissented): map (u32, u32, T::AccountId) => bool;
+      // balance held by the dissenting holders in a buyout round, at the time they dissented
+      DissentingBalance get(dissenting_balance): map (u32, u32) => T::TokenBalance;
+      // id of the next escrow
+      EscrowId get(escrow_id): u64;
+      // open escrows
+      Escrows get(escrow): map u64 => Option<Escrow<T::AccountId, T::TokenBalance>>;
+      // number of open escrows and hashed timelocks of a token
+      OpenHolds get(open_holds): map u32 => u32;
+      // system accounts (e.g. pools, bridges) whose transfers between each other are not evented one by one
+      QuietAccounts get(is_quiet_account): map T::AccountId => bool;
+      // tokenid, number and volume of the quiet transfers in the current block
+      QuietTransferTotals get(quiet_transfer_totals): Vec<(u32, u32, T::TokenBalance)>;
+      // open hashed timelocks, keyed by the keccak-256 hash of their preimage
+      Htlcs get(htlc): map H256 => Option<Htlc<T::AccountId, T::TokenBalance, T::BlockNumber>>;
+      // crowdsale of a token, kept until it is finalised and every buyer has claimed
+      Sales get(sale): map u32 => Option<Sale<T::AccountId, T::TokenBalance, T::Balance, T::BlockNumber>>;
+      // tokens bought and native balance paid by a buyer in the crowdsale of a token
+      Purchases get(purchase_of): map (u32, T::AccountId) => (T::TokenBalance, T::Balance);
+      // fair launches by token, a sale of a token without an entry is first come first served
+      FairLaunches get(fair_launch): map u32 => Option<FairLaunch<T::TokenBalance>>;
+      // position of the first unit and amount of the order of a buyer in a fair launch
+      FairOrders get(fair_order): map (u32, T::AccountId) => Option<(T::TokenBalance, T::TokenBalance)>;
+      // bonding curve issuance of a token
+      BondingCurves get(bonding_curve): map u32 => Option<CurveIssuance<T::AccountId, T::TokenBalance, T::Balance>>;
+      // creator and native balance of the issuer deposit of a token
+      IssuerDeposits get(issuer_deposit_of): map u32 => Option<(T::AccountId, T::Balance)>;
+      // open proposal to correct the metadata of a token
+      ModerationProposals get(moderation_proposal): map u32 => Option<ModerationProposal<T::AccountId, T::Balance>>;
+      // id of the next liquidity pool
+      PoolCount get(pool_count): u32;
+      // liquidity pools
+      Pools get(pool): map u32 => Option<Pool<T::AccountId>>;
+      // liquidity pool of a pair of tokens, keyed by the lower tokenid first
+      PoolOf get(pool_of): map (u32, u32) => Option<u32>;
+      // whether a token is in a liquidity pool, pools are never removed
+      PooledTokens get(is_pooled): map u32 => bool;
+      // price candles of a pool by ring buffer slot, the slot of a period is its index modulo max candles
+      Candles get(candle): map (u32, u32) => Option<Candle<T::TokenBalance>>;
+  }
+  add_extra_genesis {
+      // tokens to be created at genesis
+      // name, ticker, total supply, owner and initial balances
+      // the initial balances are taken out of the total supply, the owner gets the rest
+      config(tokens): Vec<(Vec<u8>, Vec<u8>, T::TokenBalance, T::AccountId, Vec<(T::AccountId, T::TokenBalance)>)>;
+      // dev only: number of synthetic tokens and balances to fill the registry with
+      // used to benchmark block import and storage proof sizes under multi-token load
+      // must be zero for real chains
+      config(synthetic_tokens): u32;
+      config(synthetic_balances): u32;
+      // tokens migrated from an ethereum erc20 snapshot: name, ticker, owner and the balance of every ethereum address
+      // the balances can be claimed with a signature of the ethereum key
+      // must be empty unless the runtime is built with the ethereum-migration feature
+      config(ethereum_snapshots): Vec<(Vec<u8>, Vec<u8>, T::AccountId, Vec<(EthereumAddress, T::TokenBalance)>)>;
+
+      build(|storage: &mut runtime_primitives::StorageMap, _: &mut runtime_primitives::ChildrenStorageMap, config: &GenesisConfig<T>| {
+          with_storage(storage, || {
+              for (name, ticker, total_supply, owner, endowed_accounts) in config.tokens.clone() {
+                  let token_id = <Module<T>>::_init(owner.clone(), name, ticker, total_supply, None, false, false)
+                      .expect("genesis token must be valid");
+
+                  let mut owner_balance = total_supply;
+                  for (who, value) in endowed_accounts {
+                      owner_balance = math::checked_sub(owner_balance, value, "genesis balances cannot exceed the total supply")
+                          .expect("genesis balances cannot exceed the total supply");
+                      let balance = <Module<T>>::balance_of(token_id, &who);
+                      <Module<T>>::set_balance(token_id, &who, math::saturating_add(balance, value));
+                  }
+                  <Module<T>>::set_balance(token_id, &owner, owner_balance);
+              }
+
+              // migrated tokens are only built by nodes with the ethereum-migration feature
+              #[cfg(feature = "ethereum-migration")]
+              for (name, ticker, owner, claims) in config.ethereum_snapshots.clone() {
+                  <Module<T>>::build_ethereum_snapshot(name, ticker, owner, claims);
+              }
+              #[cfg(not(feature = "ethereum-migration"))]
+              assert!(config.ethereum_snapshots.is_empty(), "ethereum snapshots need the ethereum-migration feature");
+
+              <Module<T>>::build_synthetic_state(config.synthetic_tokens, config.synthetic_balances);
+          });
+      });
+  }
+}
+
+// balances mapping for a token and account
+// keyed by the token first, so that the balances of one token share a storage prefix
+pub struct Balances<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> StorageDoubleMap for Balances<T> {
+    type Key1 = u32;
+    type Key2 = T::AccountId;
+    type Value = T::TokenBalance;
+
+    const PREFIX: &'static [u8] = b"Erc20 Balances";
+}
+
+// allowance mapping for a token, owner and spender
+// keyed by the token and owner first, so that the approvals of an owner share a storage prefix
+// the owner is user controlled, so the first key is hashed with blake2
+pub struct Allowances<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> StorageDoubleMap for Allowances<T> {
+    type Key1 = (u32, T::AccountId);
+    type Key2 = T::AccountId;
+    type Value = T::TokenBalance;
+
+    const PREFIX: &'static [u8] = b"Erc20 Allowances";
+
+    fn key1_hash(k1: &Self::Key1) -> Vec<u8> {
+        runtime_io::blake2_256(&k1.encode()).to_vec()
+    }
+}
+
+// events
+decl_event!(
+    pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, BlockNumber = <T as system::Trait>::BlockNumber, Balance = <T as self::Trait>::TokenBalance, Hash = <T as system::Trait>::Hash, NativeBalance = <T as balances::Trait>::Balance {
+        // event when a new token is created
+        // tokenid, owner, name, ticker, total supply
+        TokenCreated(u32, AccountId, Vec<u8>, Vec<u8>, Balance),
+        // event for transfer of tokens
+        // tokenid, from, to, value, optional context tag of the caller
+        Transfer(u32, AccountId, AccountId, Balance, Option<u32>),
+        // event when an approval is made
+        // tokenid, owner, spender, value
+        Approval(u32, AccountId, AccountId, Balance),
+        // event when the owner offers to mint tokens to an account
+        // offerid, tokenid, to, value
+        MintOffered(u64, u32, AccountId, Balance),
+        // event when a mint offer is withdrawn, rejected or removed after expiring
+        // offerid
+        MintOfferCancelled(u64),
+        // event when a holder gives its whole balance to the owner of the token to free the slot
+        // tokenid, holder, owner, value
+        BalanceDiscarded(u32, AccountId, AccountId, Balance),
+        // event when new tokens are minted
+        // tokenid, to, value
+        Minted(u32, AccountId, Balance),
+        // event when tokens are burned
+        // tokenid, from, value
+        Burned(u32, AccountId, Balance),
+        // event when an account registers guardians
+        // account, threshold
+        GuardiansSet(AccountId, u32),
+        // event when an account removes its guardians
+        // account
+        GuardiansRemoved(AccountId),
+        // event when the recovery of an account is started
+        // lost account, new account
+        RecoveryStarted(AccountId, AccountId),
+        // event when a guardian approves a recovery
+        // lost account, guardian
+        RecoveryApproved(AccountId, AccountId),
+        // event when a recovery is cancelled by the original account
+        // lost account
+        RecoveryCancelled(AccountId),
+        // event when a recovery is completed
+        // lost account, new account
+        RecoveryCompleted(AccountId, AccountId),
+        // event when a token is paused by its owner
+        // tokenid
+        Paused(u32),
+        // event when a token is unpaused by its owner
+        // tokenid
+        Unpaused(u32),
+        // event when a token is denied by governance
+        // tokenid
+        TokenDenied(u32),
+        // event when a token is removed from the denylist by governance
+        // tokenid
+        TokenAllowed(u32),
+        // event when an account is frozen for a token
+        // tokenid, account
+        AccountFrozen(u32, AccountId),
+        // event when an account is thawed for a token
+        // tokenid, account
+        AccountThawed(u32, AccountId),
+        // event when an external asset id is mapped to a token
+        // registry, external asset id, tokenid
+        ExternalAssetMapped(u8, Vec<u8>, u32),
+        // event when the mapping of an external asset id is removed
+        // registry, external asset id, tokenid
+        ExternalAssetUnmapped(u8, Vec<u8>, u32),
+        // event when tokens are moved by the force origin
+        // tokenid, from, to, value
+        ForcedTransfer(u32, AccountId, AccountId, Balance),
+        // event when an account is added to the blacklist of a token
+        // tokenid, account
+        Blacklisted(u32, AccountId),
+        // event when an account is removed from the blacklist of a token
+        // tokenid, account
+        RemovedFromBlacklist(u32, AccountId),
+        // event when the owner changes the transfer mode of a token
+        // tokenid, transfer mode
+        TransferModeChanged(u32, TransferMode),
+        // event when an account is added to the whitelist of a token
+        // tokenid, account
+        Whitelisted(u32, AccountId),
+        // event when an account is removed from the whitelist of a token
+        // tokenid, account
+        RemovedFromWhitelist(u32, AccountId),
+        // event when the owner schedules the sunset of the admin rights of a token
+        // tokenid, block number
+        AdminSunsetScheduled(u32, BlockNumber),
+        // event when a transfer intent is queued
+        // tokenid, from, to, value
+        IntentSubmitted(u32, AccountId, AccountId, Balance),
+        // event when a netted intent residual could not be transferred
+        // tokenid, from, to, value
+        IntentSettlementFailed(u32, AccountId, AccountId, Balance),
+        // event when queued intents are settled
+        // tokenid, number of intents settled
+        IntentsSettled(u32, u32),
+        // event when the owner changes whether a token is burnable
+        // tokenid, burnable
+        BurnableChanged(u32, bool),
+        // event when a migration program is created
+        // old tokenid, new tokenid, deadline
+        SwapProgramCreated(u32, u32, BlockNumber),
+        // event when a holder swaps old tokens for new tokens
+        // old tokenid, new tokenid, account, old value, new value
+        Swapped(u32, u32, AccountId, Balance, Balance),
+        // event for a transfer with an attached memo
+        // tokenid, from, to, value, memo
+        TransferWithMemo(u32, AccountId, AccountId, Balance, Vec<u8>),
+        // event when an approval is given an expiry
+        // tokenid, owner, spender, expiry block number
+        ApprovalExpiry(u32, AccountId, AccountId, BlockNumber),
+        // event when the owner changes the minimum balance of a token
+        // tokenid, minimum balance
+        MinBalanceChanged(u32, Balance),
+        // event when a balance below the minimum balance is removed
+        // tokenid, account, dust value
+        DustLost(u32, AccountId, Balance),
+        // event when a spender delegates part of its allowance
+        // tokenid, owner, spender, sub-spender, value
+        SubApproval(u32, AccountId, AccountId, AccountId, Balance),
+        // event when an owner revokes an allowance and its sub-allowances
+        // tokenid, owner, spender
+        AllowanceRevoked(u32, AccountId, AccountId),
+        // event when the transfers of a token in a block are committed
+        // tokenid, block number, merkle root of the transfers
+        TransfersCommitted(u32, BlockNumber, Hash),
+        // event when a fee is charged on a transfer
+        // tokenid, payer, receiver of the fee, fee
+        FeePaid(u32, AccountId, AccountId, Balance),
+        // event when a logic module change is scheduled
+        // tokenid, kind, new module id, block from which it can be applied
+        LogicScheduled(u32, LogicKind, Option<u32>, BlockNumber),
+        // event when a logic module change is applied
+        // tokenid, kind, new module id
+        LogicChanged(u32, LogicKind, Option<u32>),
+        // event when a balance drops below the low balance threshold of the account
+        // tokenid, account, new balance
+        LowBalance(u32, AccountId, Balance),
+        // event when a page of holders is written to a balance snapshot that is not complete yet
+        // tokenid, snapshot id, holders written, holder count
+        SnapshotProgressed(u32, u32, u32, u32),
+        // event when a balance snapshot of a token is taken
+        // tokenid, snapshot id, child trie root
+        SnapshotTaken(u32, u32, Vec<u8>),
+        // event when the destruction of a token starts
+        // tokenid
+        DestroyStarted(u32),
+        // event when balances of a token being destroyed are removed
+        // tokenid, removed accounts, remaining accounts
+        AccountsDestroyed(u32, u32, u32),
+        // event when approvals of a token being destroyed are removed
+        // tokenid, remaining approvals
+        ApprovalsDestroyed(u32, u32),
+        // event when escrows and hashed timelocks of a token being destroyed are refunded
+        // tokenid, remaining escrows and hashed timelocks
+        HoldsDestroyed(u32, u32),
+        // event when unclaimed ethereum balances of a token being destroyed are removed
+        // tokenid, remaining unclaimed balances
+        ClaimsDestroyed(u32, u32),
+        // event when a token is destroyed
+        // tokenid
+        TokenDestroyed(u32),
+        // event when an account is locked down by its guardian
+        // account
+        LockedDown(AccountId),
+        // event when a locked down account requests lifting the lockdown
+        // account, block from which it can be lifted
+        UnlockRequested(AccountId, BlockNumber),
+        // event when a lockdown is lifted
+        // account
+        Unlocked(AccountId),
+        // event when the name, ticker or metadata hash of a token are updated
+        // tokenid, name, ticker, metadata hash
+        MetadataUpdated(u32, Vec<u8>, Vec<u8>, Option<H256>),
+        // event when the metadata of a token is frozen
+        // tokenid
+        MetadataFrozen(u32),
+        // event when the metadata manager of a token is granted or revoked
+        // tokenid, manager, none if revoked
+        MetadataManagerSet(u32, Option<AccountId>),
+        // event when the uri of a token is updated
+        // tokenid, uri
+        UriUpdated(u32, Option<Vec<u8>>),
+        // event when the decimals of a token are set
+        // tokenid, decimals
+        DecimalsSet(u32, u8),
+        // event when the balance of an ethereum address is claimed
+        // tokenid, ethereum address, account, value
+        Claimed(u32, EthereumAddress, AccountId, Balance),
+        // event when a token creation fee is paid
+        // creator, fee in the native currency
+        CreationFeePaid(AccountId, NativeBalance),
+        // event when a token changes where its transfer fees are paid to
+        // tokenid, whether the fees go to the treasury
+        FeeDestinationChanged(u32, bool),
+        // event when the burn rate on transfers of a token changes
+        // tokenid, parts per million
+        TransferBurnRateChanged(u32, u32),
+        // event when the rounding policy of a token changes
+        // tokenid, rounding, none for the default rounding of each charge
+        RoundingPolicyChanged(u32, Option<Rounding>),
+        // event when a credit line is granted or its limit changes
+        // tokenid, grantor, borrower, limit
+        CreditLineGranted(u32, AccountId, AccountId, Balance),
+        // event when a credit line is closed
+        // tokenid, grantor, borrower
+        CreditLineClosed(u32, AccountId, AccountId),
+        // event when a borrower draws on its credit line
+        // tokenid, borrower, grantor, value
+        CreditDrawn(u32, AccountId, AccountId, Balance),
+        // event when a borrower repays debt to its grantor
+        // tokenid, borrower, grantor, value
+        CreditRepaid(u32, AccountId, AccountId, Balance),
+        // event when a receipt of a multi-leg operation is stored
+        // receipt id, hash of the execution
+        ReceiptStored(u64, Hash),
+        // event when a lock on a balance is set
+        // tokenid, lock id, account, amount
+        LockSet(u32, LockIdentifier, AccountId, Balance),
+        // event when a lock on a balance is removed
+        // tokenid, lock id, account
+        LockRemoved(u32, LockIdentifier, AccountId),
+        // event when tokens are reserved
+        // tokenid, account, value
+        Reserved(u32, AccountId, Balance),
+        // event when reserved tokens are moved back to the free balance
+        // tokenid, account, value
+        Unreserved(u32, AccountId, Balance),
+        // event when reserved tokens are burned
+        // tokenid, account, value
+        ReservedSlashed(u32, AccountId, Balance),
+        // event when reserved tokens are moved to the free balance of another account
+        // tokenid, from, to, value
+        ReserveRepatriated(u32, AccountId, AccountId, Balance),
+        // event when tokens are held under a reason
+        // tokenid, reason, account, value
+        Held(u32, HoldReason, AccountId, Balance),
+        // event when held tokens are released
+        // tokenid, reason, account, value
+        Released(u32, HoldReason, AccountId, Balance),
+        // event when a buyout of a token is proposed
+        // tokenid, payout tokenid, price numerator, price denominator, end of the dissent window
+        BuyoutProposed(u32, u32, Balance, Balance, BlockNumber),
+        // event when a buyout is approved by governance
+        // tokenid
+        BuyoutApproved(u32),
+        // event when a buyout is cancelled
+        // tokenid
+        BuyoutCancelled(u32),
+        // event when a holder dissents with a buyout
+        // tokenid, holder, balance of the holder
+        Dissented(u32, AccountId, Balance),
+        // event when a holder is bought out
+        // tokenid, holder, bought out value, payout
+        SqueezedOut(u32, AccountId, Balance, Balance),
+        // event when a buyout is completed, the owner holding the whole supply
+        // tokenid
+        BuyoutCompleted(u32),
+        // event when a buyout is closed after its execution period
+        // tokenid
+        BuyoutExpired(u32),
+        // event when tokens are parked in an escrow
+        // escrow id, tokenid, payer, beneficiary, arbiter, amount
+        EscrowCreated(u64, u32, AccountId, AccountId, AccountId, Balance),
+        // event when an escrow is released to its beneficiary
+        // escrow id, beneficiary, amount
+        EscrowReleased(u64, AccountId, Balance),
+        // event when an escrow is refunded to its payer
+        // escrow id, payer, amount
+        EscrowRefunded(u64, AccountId, Balance),
+        // event when an account is flagged or unflagged as a quiet system account
+        // account, quiet
+        QuietAccountSet(AccountId, bool),
+        // event at the end of a block with quiet transfers between system accounts
+        // tokenid, number of transfers and volume for every token with quiet transfers
+        QuietTransfers(Vec<(u32, u32, Balance)>),
+        // event when tokens are locked in a hashed timelock
+        // hash, tokenid, from, to, amount, timeout
+        HtlcLocked(H256, u32, AccountId, AccountId, Balance, BlockNumber),
+        // event when a hashed timelock is claimed with its preimage
+        // hash, preimage, to, amount
+        HtlcClaimed(H256, [u8; 32], AccountId, Balance),
+        // event when a timed out hashed timelock is refunded
+        // hash, from, amount
+        HtlcRefunded(H256, AccountId, Balance),
+        // event when a crowdsale of a token is opened
+        // tokenid, issuer, price, cap, soft cap, start, end
+        SaleStarted(u32, AccountId, NativeBalance, Balance, Balance, BlockNumber, BlockNumber),
+        // event when tokens are bought in a crowdsale
+        // tokenid, buyer, amount, cost
+        Purchased(u32, AccountId, Balance, NativeBalance),
+        // event when the cap of a crowdsale is reached
+        // tokenid
+        SoldOut(u32),
+        // event when a crowdsale is finalised
+        // tokenid, whether the soft cap was reached, tokens sold, proceeds paid to the issuer
+        SaleFinalised(u32, bool, Balance, NativeBalance),
+        // event when a buyer receives the tokens bought in a successful crowdsale
+        // tokenid, buyer, amount
+        SaleTokensClaimed(u32, AccountId, Balance),
+        // event when a buyer is refunded after a crowdsale missed its soft cap
+        // tokenid, buyer, refund
+        SaleRefunded(u32, AccountId, NativeBalance),
+        // event when an oversubscribed fair launch draws the position its fills start from
+        // tokenid, position of the first filled unit among the orders
+        FairLaunchDrawn(u32, Balance),
+        // event when a token starts issuing along a bonding curve
+        // tokenid, custodian of the reserve
+        BondingCurveEnabled(u32, AccountId),
+        // event when a token stops issuing along a bonding curve
+        // tokenid
+        BondingCurveDisabled(u32),
+        // event when tokens are bought from the bonding curve of a token
+        // tokenid, buyer, amount, cost
+        CurveBought(u32, AccountId, Balance, NativeBalance),
+        // event when tokens are sold back to the bonding curve of a token
+        // tokenid, seller, amount, payout
+        CurveSold(u32, AccountId, Balance, NativeBalance),
+        // event when a correction of the metadata of a token is proposed
+        // tokenid, proposer, proposed name, proposed ticker
+        ModerationProposed(u32, AccountId, Vec<u8>, Vec<u8>),
+        // event when a metadata correction is accepted and the issuer deposit is slashed
+        // tokenid, issuer, slashed deposit
+        ModerationAccepted(u32, Option<AccountId>, NativeBalance),
+        // event when a metadata correction is rejected and the proposer's bond is slashed
+        // tokenid, proposer, slashed bond
+        ModerationRejected(u32, AccountId, NativeBalance),
+        // event when a liquidity pool is created
+        // pool id, tokenid a, tokenid b, pool account, lp tokenid
+        PoolCreated(u32, u32, u32, AccountId, u32),
+        // event when liquidity is added to a pool
+        // pool id, provider, amount of token a, amount of token b, lp tokens minted
+        LiquidityAdded(u32, AccountId, Balance, Balance, Balance),
+        // event when liquidity is removed from a pool
+        // pool id, provider, amount of token a, amount of token b, lp tokens burned
+        LiquidityRemoved(u32, AccountId, Balance, Balance, Balance),
+        // event when tokens are swapped through a pool
+        // pool id, trader, tokenid in, amount in, tokenid out, amount out
+        PoolSwapped(u32, AccountId, u32, Balance, u32, Balance),
+        // event when the dust threshold of a token is changed
+        // tokenid, threshold
+        DustThresholdChanged(u32, Balance),
+        // event when dust balances are swept into a target token
+        // account, target tokenid, number of tokens swept, amount of the target token received
+        DustSwept(AccountId, u32, u32, Balance),
+    }
+);
@@ -0,0 +1,31 @@
+// runtime api of the erc20 module
+// lets front-ends query tokens through the runtime instead of computing storage keys
+
+use rstd::prelude::*;
+use parity_codec::Codec;
+use client::decl_runtime_apis;
+use super::{TokenInfo, Candle};
+
+decl_runtime_apis! {
+    pub trait Erc20Api<AccountId, Balance> where AccountId: Codec, Balance: Codec {
+        // balance of an account for a token
+        fn balance_of(token_id: u32, who: AccountId) -> Balance;
+        // allowance an owner has given to a spender for a token
+        fn allowance(token_id: u32, owner: AccountId, spender: AccountId) -> Balance;
+        // details of a token, none if the token does not exist
+        fn token_metadata(token_id: u32) -> Option<TokenInfo<Balance>>;
+        // number of tokens created so far, token ids run from zero up to it
+        // destroyed tokens are counted as well
+        fn token_count() -> u32;
+        // ids of the tokens that exist, destroyed tokens are left out
+        fn tokens() -> Vec<u32>;
+        // tokens an account holds a non zero balance of
+        fn account_tokens(who: AccountId) -> Vec<u32>;
+        // page of the holders of a token with their balances, starting at a holder position
+        fn holders(token_id: u32, start: u32, limit: u32) -> Vec<(AccountId, Balance)>;
+        // page of the allowances given to a spender, as token id, owner and allowance, starting at a position
+        fn incoming_allowances(spender: AccountId, start: u32, limit: u32) -> Vec<(u32, AccountId, Balance)>;
+        // price candles of a pool over the last periods kept, oldest first
+        fn candles(pool_id: u32) -> Vec<Candle<Balance>>;
+    }
+}
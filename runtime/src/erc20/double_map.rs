@@ -0,0 +1,60 @@
+// storage map keyed by two keys, laid out as <prefix><hash of key1><hash of key2>
+// all the entries under one first key share a storage key prefix, so that they
+// can be enumerated off chain by prefix and removed at once on chain
+
+use rstd::prelude::*;
+use parity_codec::Codec;
+use runtime_io::{blake2_256, twox_128};
+use support::storage::unhashed;
+
+pub trait StorageDoubleMap {
+    // the first key is hashed with key1_hash, a fast hasher by default
+    type Key1: Codec;
+    // the second key is user controlled, so it is hashed with a cryptographic hasher
+    type Key2: Codec;
+    type Value: Codec + Default;
+
+    // must be unique across all the storage items of the runtime
+    const PREFIX: &'static [u8];
+
+    // hash of the first key
+    // the fast hasher is only safe for first keys assigned by the module, e.g. token ids
+    // maps whose first key is user controlled override it with a cryptographic hasher
+    fn key1_hash(k1: &Self::Key1) -> Vec<u8> {
+        twox_128(&k1.encode()).to_vec()
+    }
+
+    // storage key prefix shared by all the entries under the first key
+    fn prefix_for(k1: &Self::Key1) -> Vec<u8> {
+        let mut key = Self::PREFIX.to_vec();
+        key.extend_from_slice(&Self::key1_hash(k1));
+        key
+    }
+
+    fn full_key(k1: &Self::Key1, k2: &Self::Key2) -> Vec<u8> {
+        let mut key = Self::prefix_for(k1);
+        key.extend_from_slice(&blake2_256(&k2.encode()));
+        key
+    }
+
+    fn exists(k1: &Self::Key1, k2: &Self::Key2) -> bool {
+        unhashed::exists(&Self::full_key(k1, k2))
+    }
+
+    fn get(k1: &Self::Key1, k2: &Self::Key2) -> Self::Value {
+        unhashed::get_or_default(&Self::full_key(k1, k2))
+    }
+
+    fn insert(k1: &Self::Key1, k2: &Self::Key2, value: Self::Value) {
+        unhashed::put(&Self::full_key(k1, k2), &value)
+    }
+
+    fn remove(k1: &Self::Key1, k2: &Self::Key2) {
+        unhashed::kill(&Self::full_key(k1, k2))
+    }
+
+    // removes all the entries under the first key
+    fn remove_prefix(k1: &Self::Key1) {
+        unhashed::kill_prefix(&Self::prefix_for(k1))
+    }
+}
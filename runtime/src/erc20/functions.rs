@@ -0,0 +1,1370 @@
+// implementation of the erc20 module functions
+
+use super::*;
+
+// implementation of mudule
+// utility and private functions
+// if marked public, accessible by other modules
+impl<T: Trait> Module<T> {
+    // creates a new token owned by the given account
+    // generates an integer token_id so that all tokens are unique
+    // the balance of the owner is set to total supply
+    // internal
+    pub(super) fn _init(
+        owner: T::AccountId,
+        name: Vec<u8>,
+        ticker: Vec<u8>,
+        total_supply: T::TokenBalance,
+        max_supply: Option<T::TokenBalance>,
+        mintable: bool,
+        burnable: bool,
+    ) -> rstd::result::Result<u32, &'static str> {
+        Self::ensure_can_init(&name, &ticker, total_supply, max_supply)?;
+        let normalized_ticker = Self::normalize_ticker(&ticker);
+
+        let token_id = Self::token_id();
+        let next_token_id = token_id.checked_add(1).ok_or("overflow in calculating next token id")?;
+        <TokenId<T>>::put(next_token_id);
+
+        let token = Erc20Token {
+            total_supply,
+            transfer_mode: TransferMode::Open,
+            max_supply,
+            mintable,
+            burnable,
+        };
+        let metadata = TokenMetadata {
+            name,
+            ticker,
+            uri: None,
+            metadata_hash: None,
+            decimals: 0,
+        };
+
+        <Tokens<T>>::insert(token_id, token);
+        <Metadata<T>>::insert(token_id, metadata);
+        <Owners<T>>::insert(token_id, owner.clone());
+        <OwnedTokens<T>>::mutate(owner.clone(), |tokens| tokens.push(token_id));
+        <TickerRegistry<T>>::insert(normalized_ticker, token_id);
+        Self::set_balance(token_id, &owner, total_supply);
+
+        Ok(token_id)
+    }
+
+    // checks the max size of a token name and ticker
+    // byte arrays (vecs) with no max size should be avoided
+    pub(super) fn ensure_metadata_length(name: &[u8], ticker: &[u8]) -> Result {
+        ensure!(name.len() <= Self::max_name_length() as usize, "token name is too long");
+        ensure!(ticker.len() <= Self::max_ticker_length() as usize, "token ticker is too long");
+        Ok(())
+    }
+
+    // checks the name, ticker and supply of a new token
+    // these are all the checks _init can fail on, so callers can run them before charging any fee
+    pub(super) fn ensure_can_init(name: &[u8], ticker: &[u8], total_supply: T::TokenBalance, max_supply: Option<T::TokenBalance>) -> Result {
+        Self::ensure_metadata_length(name, ticker)?;
+
+        // tickers are unique, ignoring case
+        ensure!(!<TickerRegistry<T>>::exists(Self::normalize_ticker(ticker)), "Ticker is already registered");
+        if let Some(max_supply) = max_supply {
+            ensure!(total_supply <= max_supply, "Total supply cannot exceed the max supply");
+        }
+        Self::ensure_token_ids_available(1)
+    }
+
+    // checks that the given number of tokens can still be assigned a token id
+    pub(super) fn ensure_token_ids_available(tokens: u32) -> Result {
+        Self::token_id().checked_add(tokens).ok_or("overflow in calculating next token id")?;
+        Ok(())
+    }
+
+    // the ERC20 standard transfer function
+    // internal
+    pub(super) fn _transfer(
+        token_id: u32,
+        from: T::AccountId,
+        to: T::AccountId,
+        value: T::TokenBalance,
+    ) -> Result {
+        Self::_transfer_with_context(token_id, from, to, value, None)
+    }
+
+    // the ERC20 standard transfer function with an optional context tag
+    // the context is included in the transfer event, it is not stored
+    // internal
+    pub(super) fn _transfer_with_context(
+        token_id: u32,
+        from: T::AccountId,
+        to: T::AccountId,
+        value: T::TokenBalance,
+        context: Option<u32>,
+    ) -> Result {
+        // every leg (credit, value, burn, fee, repayment) is checked before any is written
+        let mut ledger = Ledger::new(token_id);
+        ledger.transfer(&from, &to, value, context)?;
+        ledger.commit();
+        Ok(())
+    }
+
+    // moves tokens from one account to another without checking any transfer restrictions
+    // callers are responsible for the restriction checks and the event
+    // internal
+    pub(super) fn _move(
+        token_id: u32,
+        from: T::AccountId,
+        to: T::AccountId,
+        value: T::TokenBalance,
+    ) -> Result {
+        let mut ledger = Ledger::new(token_id);
+        ledger.move_balance(&from, &to, value)?;
+        ledger.commit();
+        Ok(())
+    }
+
+    // checks the restrictions on moving a token between two accounts
+    // called before any balance is changed by a transfer
+    pub(super) fn ensure_can_transfer(token_id: u32, from: &T::AccountId, to: &T::AccountId) -> Result {
+        ensure!(!Self::is_denied(token_id), "Token is denied");
+        ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+        ensure!(!Self::is_paused(token_id), "Token transfers are paused");
+        if let Some(program) = Self::swap_program(token_id) {
+            ensure!(<system::Module<T>>::block_number() <= program.deadline, "Token has been migrated");
+        }
+        ensure!(!Self::is_locked_down(from.clone()), "Sender account is locked down");
+        ensure!(!Self::is_frozen((token_id, from.clone())), "Sender account is frozen");
+        ensure!(!Self::is_frozen((token_id, to.clone())), "Receiver account is frozen");
+        ensure!(!Self::is_blacklisted((token_id, from.clone())), "Sender account is blacklisted");
+        ensure!(!Self::is_blacklisted((token_id, to.clone())), "Receiver account is blacklisted");
+        ensure!(T::ComplianceCheck::can_send(token_id, from), "Sender account failed the compliance check");
+        ensure!(T::ComplianceCheck::can_receive(token_id, to), "Receiver account failed the compliance check");
+        if Self::token_details(token_id).transfer_mode == TransferMode::Whitelisted {
+            ensure!(Self::is_whitelisted((token_id, to.clone())), "Receiver account is not whitelisted");
+        }
+        Ok(())
+    }
+
+    // checks that an account can be credited with a token outside of a transfer, as by a claim
+    // holds the receiver to the receiver checks of a transfer
+    pub(super) fn ensure_can_receive(token_id: u32, to: &T::AccountId) -> Result {
+        ensure!(!Self::is_denied(token_id), "Token is denied");
+        ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+        ensure!(!Self::is_frozen((token_id, to.clone())), "Receiver account is frozen");
+        ensure!(!Self::is_blacklisted((token_id, to.clone())), "Receiver account is blacklisted");
+        ensure!(T::ComplianceCheck::can_receive(token_id, to), "Receiver account failed the compliance check");
+        if Self::token_details(token_id).transfer_mode == TransferMode::Whitelisted {
+            ensure!(Self::is_whitelisted((token_id, to.clone())), "Receiver account is not whitelisted");
+        }
+        Self::ensure_can_hold(token_id, to)
+    }
+
+    // writes the balance of an account for a token
+    // a zero balance removes the entry, so that state does not grow with every past holder
+    // an account only owns a token while it has a non zero balance
+    // keeps the holder index of the token in sync with the free and reserved balance entries
+    // notifies the account when the balance drops below its low balance threshold
+    pub(super) fn set_balance(token_id: u32, who: &T::AccountId, balance: T::TokenBalance) {
+        let held = Self::holds_token(token_id, who);
+        if let Some(threshold) = Self::low_balance_threshold((token_id, who.clone())) {
+            if balance < threshold && Self::balance_of(token_id, who) >= threshold {
+                Self::deposit_event(RawEvent::LowBalance(token_id, who.clone(), balance));
+            }
+        }
+        if balance.is_zero() {
+            <Balances<T>>::remove(&token_id, who);
+        } else {
+            <Balances<T>>::insert(&token_id, who, balance);
+        }
+        Self::update_holder(token_id, who, held);
+    }
+
+    // whether an account holds a token, i.e. has a free or a reserved balance of it
+    // held balances are reserved, so they count as well
+    // holders are indexed and count towards the tokens of the account while they hold the token
+    pub fn holds_token(token_id: u32, who: &T::AccountId) -> bool {
+        Self::has_balance(token_id, who) || <ReservedBalanceOf<T>>::exists((token_id, who.clone()))
+    }
+
+    // adds an account to or removes it from the holders of a token
+    // after a write to its free or reserved balance, given whether it held the token before
+    fn update_holder(token_id: u32, who: &T::AccountId, held: bool) {
+        let holds = Self::holds_token(token_id, who);
+        if held && !holds {
+            Self::remove_holder(token_id, who);
+        } else if !held && holds {
+            Self::add_holder(token_id, who);
+        }
+    }
+
+    // appends an account to the holders of a token
+    // and the token to the tokens held by the account
+    fn add_holder(token_id: u32, who: &T::AccountId) {
+        <AccountTokens<T>>::mutate(who.clone(), |tokens| if !tokens.contains(&token_id) { tokens.push(token_id) });
+        let count = Self::holder_count(token_id);
+        <HolderAt<T>>::insert((token_id, count), who.clone());
+        <HolderIndex<T>>::insert((token_id, who.clone()), count);
+        <HolderCount<T>>::insert(token_id, count.saturating_add(1));
+    }
+
+    // removes an account from the holders of a token
+    // the last holder is moved into the freed slot so that the holders stay contiguous
+    // an account that was never indexed leaves the positions untouched
+    fn remove_holder(token_id: u32, who: &T::AccountId) {
+        <AccountTokens<T>>::mutate(who.clone(), |tokens| tokens.retain(|t| *t != token_id));
+        let index = match <HolderIndex<T>>::take((token_id, who.clone())) {
+            Some(index) => index,
+            None => return,
+        };
+        let count = Self::holder_count(token_id);
+        if count == 0 {
+            return;
+        }
+        let last = count - 1;
+        if index != last {
+            let last_holder = <HolderAt<T>>::take((token_id, last));
+            <HolderAt<T>>::insert((token_id, index), last_holder.clone());
+            <HolderIndex<T>>::insert((token_id, last_holder), index);
+        } else {
+            <HolderAt<T>>::remove((token_id, last));
+        }
+        <HolderCount<T>>::insert(token_id, last);
+    }
+
+    // balance of an account for a token
+    pub fn balance_of(token_id: u32, who: &T::AccountId) -> T::TokenBalance {
+        Self::migrate_balance(token_id, who);
+        <Balances<T>>::get(&token_id, who)
+    }
+
+    // whether an account owns a token, i.e. has a balance entry for it
+    pub fn has_balance(token_id: u32, who: &T::AccountId) -> bool {
+        Self::migrate_balance(token_id, who);
+        <Balances<T>>::exists(&token_id, who)
+    }
+
+    // moves a balance stored under the legacy tuple keyed map into the double map
+    // and adds balances written before the holder index existed to the holders
+    // storage cannot be iterated on chain, so entries are migrated lazily when touched
+    fn migrate_balance(token_id: u32, who: &T::AccountId) {
+        let key = (token_id, who.clone());
+        if <BalanceOf<T>>::exists(key.clone()) {
+            <Balances<T>>::insert(&token_id, who, <BalanceOf<T>>::take(key.clone()));
+        }
+        if !<HolderIndex<T>>::exists(key) && <Balances<T>>::exists(&token_id, who) {
+            Self::add_holder(token_id, who);
+        }
+    }
+
+    // applies a batch of transfers in order on a ledger, without writing anything
+    // every leg gets all the checks, fees, burns and credit lines of a single transfer
+    // and sees the balances left by the legs before it
+    // lets batch calls validate every leg before applying any of them by committing the ledger
+    pub(super) fn plan_transfers(token_id: u32, transfers: &[(T::AccountId, T::AccountId, T::TokenBalance)]) -> rstd::result::Result<Ledger<T>, &'static str> {
+        let mut ledger = Ledger::new(token_id);
+        for (from, to, value) in transfers.iter() {
+            ledger.transfer(from, to, *value, None)?;
+        }
+        Ok(ledger)
+    }
+
+    // checks that an allowance has not expired
+    pub(super) fn ensure_allowance_active(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) -> Result {
+        if let Some(expires_at) = Self::allowance_expiry((token_id, owner.clone(), spender.clone())) {
+            ensure!(<system::Module<T>>::block_number() < expires_at, "Allowance has expired.");
+        }
+        Ok(())
+    }
+
+    // checks the restrictions on an owner approving a spender for a token
+    pub(super) fn ensure_can_approve(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) -> Result {
+        ensure!(!Self::is_denied(token_id), "Token is denied");
+        ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+        ensure!(!Self::is_blacklisted((token_id, owner.clone())), "Owner account is blacklisted");
+        ensure!(!Self::is_blacklisted((token_id, spender.clone())), "Spender account is blacklisted");
+        Ok(())
+    }
+
+    // checks that the given account is the owner of the token
+    // also fails once the admin rights of the token have been sunset
+    pub(super) fn ensure_owner(token_id: u32, who: &T::AccountId) -> Result {
+        let owner = Self::owner_of(token_id).ok_or("Token does not exist")?;
+        ensure!(owner == *who, "Only the token owner can do this");
+        Self::ensure_admin_active(token_id)
+    }
+
+    // checks that an account is the owner or the metadata manager of a token
+    pub(super) fn ensure_metadata_editor(token_id: u32, who: &T::AccountId) -> Result {
+        let owner = Self::owner_of(token_id).ok_or("Token does not exist")?;
+        ensure!(owner == *who || Self::metadata_manager(token_id).as_ref() == Some(who), "Only the token owner or metadata manager can do this");
+        Self::ensure_admin_active(token_id)
+    }
+
+    // checks that the admin rights of the token have not been sunset
+    pub(super) fn ensure_admin_active(token_id: u32) -> Result {
+        if let Some(sunset) = Self::admin_sunset(token_id) {
+            ensure!(<system::Module<T>>::block_number() < sunset, "Admin rights of this token have been sunset");
+        }
+        Ok(())
+    }
+
+    // mints new tokens to an account
+    // increases both the total supply and the balance of the account
+    // internal
+    pub(super) fn _mint(token_id: u32, to: T::AccountId, value: T::TokenBalance) -> Result {
+        Self::ensure_can_mint(token_id, &to, value)?;
+        let mut token = Self::token_details(token_id);
+        token.total_supply = token.total_supply + value;
+        <Tokens<T>>::insert(token_id, token);
+        let updated_to_balance = Self::balance_of(token_id, &to) + value;
+        Self::set_balance(token_id, &to, updated_to_balance);
+
+        Self::deposit_event(RawEvent::Minted(token_id, to, value));
+        Ok(())
+    }
+
+    // burns tokens from an account
+    // decreases both the total supply and the balance of the account
+    // internal
+    pub(super) fn _burn(token_id: u32, from: T::AccountId, value: T::TokenBalance) -> Result {
+        Self::ensure_can_burn(token_id, &from, value)?;
+        let mut token = Self::token_details(token_id);
+        let updated_from_balance = Self::balance_of(token_id, &from) - value;
+
+        token.total_supply = token.total_supply - value;
+        <Tokens<T>>::insert(token_id, token);
+        Self::set_balance(token_id, &from, updated_from_balance);
+
+        Self::deposit_event(RawEvent::Burned(token_id, from, value));
+        Ok(())
+    }
+
+    // checks that a mint can succeed, without writing anything
+    // callers that mint after another write check this first
+    pub(super) fn ensure_can_mint(token_id: u32, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        ensure!(<Tokens<T>>::exists(token_id), "Token does not exist");
+        ensure!(!Self::is_destroying(token_id), "Token is being destroyed");
+        let token = Self::token_details(token_id);
+        ensure!(token.mintable, "Token is not mintable");
+        let updated_total_supply = math::checked_add(token.total_supply, value, "overflow in calculating total supply")?;
+        if let Some(max_supply) = token.max_supply {
+            ensure!(updated_total_supply <= max_supply, "Minting would exceed the max supply");
+        }
+        math::checked_add(Self::balance_of(token_id, to), value, "overflow in calculating balance")?;
+        Self::ensure_can_hold(token_id, to)
+    }
+
+    // checks that a burn can succeed, without writing anything
+    // callers that burn after another write check this first
+    pub(super) fn ensure_can_burn(token_id: u32, from: &T::AccountId, value: T::TokenBalance) -> Result {
+        ensure!(!Self::is_locked_down(from.clone()), "Sender account is locked down");
+        ensure!(Self::has_balance(token_id, from), "Account does not own this token");
+        ensure!(Self::balance_of(token_id, from) >= value, "Not enough balance.");
+        Self::ensure_unlocked(token_id, from, value)?;
+        math::checked_sub(Self::token_details(token_id).total_supply, value, "overflow in calculating total supply")?;
+        Ok(())
+    }
+
+    // moves all the balances and token ownerships of the lost account to the new account
+    // only the tokens indexed for the lost account are visited, so the cost grows with what it holds and owns
+    // reserved balances, locks and credit lines belong to other modules and cannot be moved for them,
+    // so recovery is refused until they are released
+    // the allowances given by the lost account for the tokens it holds and the allowances given to it are revoked
+    // internal
+    pub(super) fn _recover(lost: T::AccountId, new_account: T::AccountId) -> Result {
+        let held_tokens = Self::account_tokens(lost.clone());
+        ensure!(!Self::is_locked_down(lost.clone()), "Lost account is locked down");
+
+        // check every token first so that nothing is written if any token fails
+        let mut new_tokens = 0u32;
+        for token_id in held_tokens.iter() {
+            let key = (*token_id, lost.clone());
+            ensure!(Self::reserved_balance_of(key.clone()).is_zero(), "Lost account has reserved balances");
+            ensure!(Self::locks(key.clone()).is_empty(), "Lost account has locked balances");
+            ensure!(Self::credit_line(key).is_none(), "Lost account has a credit line");
+
+            let lost_balance = Self::balance_of(*token_id, &lost);
+            let new_balance = Self::balance_of(*token_id, &new_account);
+            math::checked_add(new_balance, lost_balance, "overflow in calculating balance")?;
+            if !Self::holds_token(*token_id, &new_account) {
+                new_tokens = new_tokens.saturating_add(1);
+            }
+        }
+        let account_tokens = (Self::account_tokens(new_account.clone()).len() as u32).saturating_add(new_tokens);
+        ensure!(account_tokens <= Self::max_account_tokens(), "Account holds too many tokens");
+
+        for token_id in held_tokens.iter() {
+            for spender in Self::approved_spenders((*token_id, lost.clone())) {
+                Self::_revoke_allowance(*token_id, lost.clone(), spender);
+            }
+        }
+        // revoking removes the last allowance of the spender, so it is read again on every pass
+        for _ in 0..Self::incoming_allowance_count(lost.clone()) {
+            let last = Self::incoming_allowance_count(lost.clone()).saturating_sub(1);
+            let (token_id, owner) = Self::incoming_allowance_at((lost.clone(), last));
+            Self::_revoke_allowance(token_id, owner, lost.clone());
+        }
+
+        for token_id in held_tokens {
+            let lost_balance = Self::balance_of(token_id, &lost);
+            let new_balance = Self::balance_of(token_id, &new_account);
+            let updated_balance = math::checked_add(new_balance, lost_balance, "overflow in calculating balance")?;
+            Self::set_balance(token_id, &lost, Zero::zero());
+            Self::set_balance(token_id, &new_account, updated_balance);
+
+            T::TransferHandler::on_transfer(token_id, &lost, &new_account, lost_balance);
+            Self::record_transfer(token_id, &lost, &new_account, lost_balance);
+            Self::deposit_event(RawEvent::Transfer(token_id, lost.clone(), new_account.clone(), lost_balance, None));
+        }
+
+        for token_id in <OwnedTokens<T>>::take(lost.clone()) {
+            <Owners<T>>::insert(token_id, new_account.clone());
+            <OwnedTokens<T>>::mutate(new_account.clone(), |tokens| tokens.push(token_id));
+        }
+
+        Ok(())
+    }
+
+    // converts an asset id of another registry to a token id
+    // the external id is decoded from its SCALE encoding
+    pub fn token_id_from_external<E: Codec>(registry: u8, external_id: &E) -> Option<u32> {
+        Self::token_of_external_asset((registry, external_id.encode()))
+    }
+
+    // converts a token id to the asset id of another registry
+    pub fn token_id_to_external<E: Codec>(token_id: u32, registry: u8) -> Option<E> {
+        Self::external_asset_of_token((token_id, registry))
+            .and_then(|external_id| E::decode(&mut &external_id[..]))
+    }
+
+    // normalizes a ticker for the ticker registry
+    // ascii letters are converted to uppercase, so tickers differing only in case collide
+    pub(super) fn normalize_ticker(ticker: &[u8]) -> Vec<u8> {
+        let mut normalized_ticker = ticker.to_vec();
+        normalized_ticker.make_ascii_uppercase();
+        normalized_ticker
+    }
+
+    // looks up the token id registered for a ticker, ignoring case
+    // lets wallets and other modules resolve tokens by ticker
+    pub fn token_id_of(ticker: &[u8]) -> Option<u32> {
+        Self::ticker_registry(Self::normalize_ticker(ticker))
+    }
+
+    // whether an account holds at least the given balance of a token
+    pub fn holds_at_least(token_id: u32, who: &T::AccountId, min_balance: T::TokenBalance) -> bool {
+        Self::balance_of(token_id, who) >= min_balance
+    }
+
+    // the cap on the total supply of a token, if any
+    pub fn max_supply(token_id: u32) -> Option<T::TokenBalance> {
+        Self::token_details(token_id).max_supply
+    }
+
+    // creates a token migrated from an ethereum erc20 snapshot, called at genesis
+    // the supply starts out unclaimed, each ethereum address claims its balance with a signature
+    #[cfg(feature = "ethereum-migration")]
+    pub(super) fn build_ethereum_snapshot(name: Vec<u8>, ticker: Vec<u8>, owner: T::AccountId, claims: Vec<(EthereumAddress, T::TokenBalance)>) {
+        let total_supply = claims.iter().fold(Zero::zero(), |total, (_, value)| math::saturating_add(total, *value));
+        let token_id = Self::_init(owner.clone(), name, ticker, total_supply, None, false, false)
+            .expect("genesis token must be valid");
+        Self::set_balance(token_id, &owner, Zero::zero());
+
+        for (address, value) in claims {
+            let unclaimed = match Self::ethereum_claim((token_id, address)) {
+                Some(unclaimed) => unclaimed,
+                None => {
+                    <UnclaimedCount<T>>::mutate(token_id, |count| *count = count.saturating_add(1));
+                    Zero::zero()
+                }
+            };
+            <EthereumClaims<T>>::insert((token_id, address), math::saturating_add(unclaimed, value));
+        }
+    }
+
+    // fills the registry with deterministic synthetic tokens and balances
+    // the balances are spread over the tokens round robin, 1000 units each
+    // the accounts are derived by hashing their index
+    // dev only, called at genesis
+    #[cfg(feature = "std")]
+    pub(super) fn build_synthetic_state(tokens: u32, balances: u32) {
+        if tokens == 0 {
+            return;
+        }
+
+        let account = |index: u32| -> T::AccountId {
+            let seed = blake2_256(&(b"erc20:synthetic", index).encode());
+            T::AccountId::decode(&mut &seed[..]).expect("account ids can be decoded from 32 bytes")
+        };
+
+        let first_token_id = Self::token_id();
+        for i in 0..tokens {
+            let name = format!("Synthetic Token {}", i).into_bytes();
+            let ticker = format!("SYN{}", i).into_bytes();
+            <Module<T>>::_init(account(0), name, ticker, Zero::zero(), None, true, true)
+                .expect("synthetic token must be valid");
+        }
+
+        let value = T::TokenBalance::sa(1000u64);
+        for j in 0..balances {
+            let token_id = first_token_id + j % tokens;
+            let who = account(j);
+
+            let mut token = Self::token_details(token_id);
+            token.total_supply = math::saturating_add(token.total_supply, value);
+            <Tokens<T>>::insert(token_id, token);
+
+            let balance = Self::balance_of(token_id, &who);
+            Self::set_balance(token_id, &who, math::saturating_add(balance, value));
+        }
+    }
+
+    // consumes the signed message nonce of an account
+    pub(super) fn use_nonce(who: &T::AccountId, nonce: u64) -> Result {
+        let next_nonce = nonce.checked_add(1).ok_or("overflow in calculating next nonce")?;
+        <Nonces<T>>::insert(who.clone(), next_nonce);
+        Ok(())
+    }
+
+    // adds a transfer to the leaves committed for the token at the end of the block
+    // a leaf is the scale encoded (from, to, value) of the transfer
+    pub(super) fn record_transfer(token_id: u32, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) {
+        let mut leaves = Self::transfer_leaves(token_id);
+        if leaves.is_empty() {
+            <TransferTokens<T>>::mutate(|tokens| tokens.push(token_id));
+        }
+        leaves.push((from, to, value).encode());
+        <TransferLeaves<T>>::insert(token_id, leaves);
+    }
+
+    // stores the merkle root of the transfers of each token touched in the block
+    // the root is the ordered trie root of the leaves, so a statement of the transfers
+    // of a token in a block can be checked against the chain with a trie proof
+    pub(super) fn commit_transfer_roots(n: T::BlockNumber) {
+        for token_id in <TransferTokens<T>>::take() {
+            let leaves = <TransferLeaves<T>>::take(token_id);
+            let items: Vec<&[u8]> = leaves.iter().map(|leaf| &leaf[..]).collect();
+            let root = T::Hashing::enumerated_trie_root(&items);
+            <TransferRoots<T>>::insert((token_id, n.clone()), root);
+            Self::deposit_event(RawEvent::TransfersCommitted(token_id, n.clone(), root));
+        }
+    }
+
+    // allowance an owner has given to a spender for a token
+    pub fn allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) -> T::TokenBalance {
+        Self::migrate_allowance(token_id, owner, spender);
+        <Allowances<T>>::get(&(token_id, owner.clone()), spender)
+    }
+
+    // whether an owner has approved a spender for a token
+    pub fn has_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) -> bool {
+        Self::migrate_allowance(token_id, owner, spender);
+        <Allowances<T>>::exists(&(token_id, owner.clone()), spender)
+    }
+
+    // writes the allowance an owner has given to a spender
+    // keeps the approved spenders of the owner and the incoming allowances of the spender
+    // in sync with the allowance entries
+    pub(super) fn write_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId, value: T::TokenBalance) {
+        let key = (token_id, owner.clone());
+        if !<Allowances<T>>::exists(&key, spender) {
+            <ApprovedSpenders<T>>::mutate(key.clone(), |spenders| spenders.push(spender.clone()));
+            <ApprovalCount<T>>::mutate(token_id, |count| *count = count.saturating_add(1));
+            Self::add_incoming_allowance(token_id, owner, spender);
+        }
+        <Allowances<T>>::insert(&key, spender, value);
+    }
+
+    pub(super) fn remove_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) {
+        let key = (token_id, owner.clone());
+        if <Allowances<T>>::exists(&key, spender) {
+            <ApprovedSpenders<T>>::mutate(key.clone(), |spenders| spenders.retain(|s| s != spender));
+            <ApprovalCount<T>>::mutate(token_id, |count| *count = count.saturating_sub(1));
+            Self::remove_incoming_allowance(token_id, owner, spender);
+        }
+        <Allowances<T>>::remove(&key, spender);
+    }
+
+    // appends an allowance to the allowances given to the spender
+    fn add_incoming_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) {
+        let count = Self::incoming_allowance_count(spender.clone());
+        <IncomingAllowanceAt<T>>::insert((spender.clone(), count), (token_id, owner.clone()));
+        <IncomingAllowanceIndex<T>>::insert((token_id, owner.clone(), spender.clone()), count);
+        <IncomingAllowanceCount<T>>::insert(spender.clone(), count.saturating_add(1));
+    }
+
+    // removes an allowance from the allowances given to the spender
+    // the last allowance is moved into the freed slot so that the positions stay contiguous, like remove_holder
+    fn remove_incoming_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) {
+        let index = match <IncomingAllowanceIndex<T>>::take((token_id, owner.clone(), spender.clone())) {
+            Some(index) => index,
+            None => return,
+        };
+        let count = Self::incoming_allowance_count(spender.clone());
+        if count == 0 {
+            return;
+        }
+        let last = count - 1;
+        if index != last {
+            let (last_token, last_owner) = <IncomingAllowanceAt<T>>::take((spender.clone(), last));
+            <IncomingAllowanceAt<T>>::insert((spender.clone(), index), (last_token, last_owner.clone()));
+            <IncomingAllowanceIndex<T>>::insert((last_token, last_owner, spender.clone()), index);
+        } else {
+            <IncomingAllowanceAt<T>>::remove((spender.clone(), last));
+        }
+        <IncomingAllowanceCount<T>>::insert(spender.clone(), last);
+    }
+
+    // moves an allowance stored under the legacy tuple keyed map into the double map
+    fn migrate_allowance(token_id: u32, owner: &T::AccountId, spender: &T::AccountId) {
+        let key = (token_id, owner.clone(), spender.clone());
+        if <Allowance<T>>::exists(key.clone()) {
+            Self::write_allowance(token_id, owner, spender, <Allowance<T>>::take(key));
+        }
+    }
+
+    // removes the allowance of a spender and invalidates its sub-allowances
+    // internal
+    pub(super) fn _revoke_allowance(token_id: u32, owner: T::AccountId, spender: T::AccountId) {
+        Self::migrate_allowance(token_id, &owner, &spender);
+
+        let key = (token_id, owner.clone(), spender.clone());
+        let epoch = Self::allowance_epoch(key.clone());
+        <AllowanceEpoch<T>>::insert(key.clone(), epoch.wrapping_add(1));
+        Self::remove_allowance(token_id, &owner, &spender);
+        <AllowanceExpiry<T>>::remove(key.clone());
+        // sub-allowances delegated before the index existed are only invalidated by the epoch
+        for sub_spender in <SubSpenders<T>>::take(key) {
+            <SubAllowances<T>>::remove((token_id, owner.clone(), spender.clone(), sub_spender));
+        }
+
+        Self::deposit_event(RawEvent::AllowanceRevoked(token_id, owner, spender));
+    }
+
+    // details of a token, none if the token does not exist
+    pub fn token_metadata(token_id: u32) -> Option<TokenInfo<T::TokenBalance>> {
+        if <Tokens<T>>::exists(token_id) {
+            Some(TokenInfo { details: Self::token_details(token_id), metadata: Self::metadata(token_id) })
+        } else {
+            None
+        }
+    }
+
+    // ids of the tokens that exist, skipping destroyed tokens
+    pub fn existing_tokens() -> Vec<u32> {
+        (0..Self::token_id()).filter(|token_id| <Tokens<T>>::exists(*token_id)).collect()
+    }
+
+    // storage key of the child trie holding a balance snapshot
+    pub fn snapshot_storage_key(token_id: u32, snapshot_id: u32) -> Vec<u8> {
+        let mut key = b":child_storage:erc20:".to_vec();
+        key.extend_from_slice(&(token_id, snapshot_id).encode());
+        key
+    }
+
+    // writes the balances of the holders at positions start to end of a token into the snapshot child trie
+    // keys are the encoded accounts, values the encoded balances
+    pub(super) fn write_snapshot(token_id: u32, snapshot_id: u32, start: u32, end: u32) {
+        let storage_key = Self::snapshot_storage_key(token_id, snapshot_id);
+        for index in start..end {
+            let who = Self::holder_at((token_id, index));
+            let balance = Self::balance_of(token_id, &who);
+            runtime_io::set_child_storage(&storage_key, &who.encode(), &balance.encode());
+        }
+    }
+
+    // checks that an account can receive a token it does not hold yet
+    // bounds the tokens index of the account
+    pub(super) fn ensure_can_hold(token_id: u32, who: &T::AccountId) -> Result {
+        if !Self::holds_token(token_id, who) {
+            ensure!((Self::account_tokens(who.clone()).len() as u32) < Self::max_account_tokens(), "Account holds too many tokens");
+        }
+        Ok(())
+    }
+
+    // page of the holders of a token with their balances
+    // starts at the given holder position, returns at most limit holders
+    // positions change as holders come and go, so pages should be read at the same block
+    pub fn holders(token_id: u32, start: u32, limit: u32) -> Vec<(T::AccountId, T::TokenBalance)> {
+        let end = start.saturating_add(limit.min(Self::max_page_size())).min(Self::holder_count(token_id));
+        (start..end).map(|index| {
+            let who = Self::holder_at((token_id, index));
+            let balance = Self::balance_of(token_id, &who);
+            (who, balance)
+        }).collect()
+    }
+
+    // checks that a ticker does not start with a reserved prefix, ignoring case
+    pub(super) fn ensure_ticker_not_reserved(ticker: &[u8]) -> Result {
+        let ticker = Self::normalize_ticker(ticker);
+        for prefix in Self::reserved_ticker_prefixes() {
+            ensure!(!ticker.starts_with(&Self::normalize_ticker(&prefix)), "Ticker prefix is reserved");
+        }
+        Ok(())
+    }
+
+    // creates a token on behalf of another runtime module
+    // reserved ticker prefixes are allowed, so that system tokens cannot be spoofed by users
+    pub fn init_system_token(
+        owner: T::AccountId,
+        name: Vec<u8>,
+        ticker: Vec<u8>,
+        total_supply: T::TokenBalance,
+        max_supply: Option<T::TokenBalance>,
+        mintable: bool,
+        burnable: bool,
+    ) -> rstd::result::Result<u32, &'static str> {
+        let token_id = Self::_init(owner.clone(), name.clone(), ticker.clone(), total_supply, max_supply, mintable, burnable)?;
+        Self::deposit_event(RawEvent::TokenCreated(token_id, owner, name, ticker, total_supply));
+        Ok(token_id)
+    }
+
+    // page of the allowances given to a spender, with their current value
+    // starts at the given position, returns at most limit allowances, like holders
+    pub fn incoming_allowances_of(spender: &T::AccountId, start: u32, limit: u32) -> Vec<(u32, T::AccountId, T::TokenBalance)> {
+        let end = start.saturating_add(limit.min(Self::max_page_size())).min(Self::incoming_allowance_count(spender.clone()));
+        (start..end).map(|index| {
+            let (token_id, owner) = Self::incoming_allowance_at((spender.clone(), index));
+            let allowance = Self::allowance(token_id, &owner, spender);
+            (token_id, owner, allowance)
+        }).collect()
+    }
+
+    // recovers the ethereum address that signed the claim of an account
+    // the signed message is "erc20:claim" followed by the scale encoded account,
+    // wrapped as an ethereum personal message
+    pub(super) fn ethereum_signer(who: &T::AccountId, signature: &EcdsaSignature) -> Option<EthereumAddress> {
+        let mut message = b"erc20:claim".to_vec();
+        message.extend_from_slice(&who.encode());
+
+        let mut prefixed = b"\x19Ethereum Signed Message:\n".to_vec();
+        prefixed.extend_from_slice(&Self::decimal_bytes(message.len()));
+        prefixed.extend_from_slice(&message);
+
+        let mut raw = [0u8; 65];
+        raw[..32].copy_from_slice(&signature.0);
+        raw[32..64].copy_from_slice(&signature.1);
+        // wallets encode the recovery id as 27 or 28
+        raw[64] = if signature.2 >= 27 { signature.2 - 27 } else { signature.2 };
+
+        let public = runtime_io::secp256k1_ecdsa_recover(&raw, &runtime_io::keccak_256(&prefixed)).ok()?;
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&runtime_io::keccak_256(&public[..])[12..]);
+        Some(address)
+    }
+
+    // ascii decimal digits of a number
+    fn decimal_bytes(mut value: usize) -> Vec<u8> {
+        let mut digits = Vec::new();
+        loop {
+            digits.push(b'0' + (value % 10) as u8);
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        digits
+    }
+
+    // current fee for creating a token
+    pub fn creation_fee() -> T::Balance {
+        let fee = Self::creation_fee_value();
+        let base_fee = Self::creation_base_fee();
+        if fee > base_fee { fee } else { base_fee }
+    }
+
+    // burns the creation fee of the given number of tokens from the native balance of the creator
+    // and reserves the issuer deposit of every token, see record_issuer_deposit
+    pub(super) fn charge_creation_fee(who: &T::AccountId, tokens: u32) -> Result {
+        let fee = Self::creation_fee().checked_mul(&T::Balance::sa(tokens as u64)).ok_or("overflow in calculating creation fee")?;
+        let deposit = Self::issuer_deposit().checked_mul(&T::Balance::sa(tokens as u64)).ok_or("overflow in calculating issuer deposit")?;
+        let total = fee.checked_add(&deposit).ok_or("overflow in calculating creation fee")?;
+        if !total.is_zero() {
+            <balances::Module<T>>::reserve(who, total).map_err(|_| "Not enough balance to pay the creation fee and issuer deposit")?;
+        }
+        if !fee.is_zero() {
+            <balances::Module<T>>::slash_reserved(who, fee);
+            Self::deposit_event(RawEvent::CreationFeePaid(who.clone(), fee));
+        }
+        <CreationsInBlock<T>>::mutate(|count| *count = count.saturating_add(tokens));
+        Ok(())
+    }
+
+    // moves the creation fee towards the creation volume of the block
+    // rises by up to an eighth when creations exceed the target, decays by up to an eighth otherwise
+    pub(super) fn update_creation_fee() {
+        let creations = <CreationsInBlock<T>>::take() as u64;
+        let target = Self::creation_target().max(1) as u64;
+        let fee = Self::creation_fee();
+        let updated_fee = if creations > target {
+            let change = math::mul_div(fee, T::Balance::sa(creations - target), T::Balance::sa(target * 8)).unwrap_or_else(|_| Zero::zero());
+            math::saturating_add(fee, change.max(T::Balance::sa(1u64)))
+        } else {
+            let change = math::mul_div(fee, T::Balance::sa(target - creations), T::Balance::sa(target * 8)).unwrap_or_else(|_| Zero::zero());
+            math::saturating_sub(fee, change)
+        };
+        let base_fee = Self::creation_base_fee();
+        <CreationFee<T>>::put(if updated_fee > base_fee { updated_fee } else { base_fee });
+    }
+
+    // account transfer fees of a token are paid to
+    // the treasury if the token directs its fees there and the runtime has one, the token owner otherwise
+    pub fn fee_receiver(token_id: u32) -> Option<T::AccountId> {
+        if Self::fees_to_treasury(token_id) {
+            if let Some(treasury) = T::FeeDestination::treasury() {
+                return Some(treasury);
+            }
+        }
+        Self::owner_of(token_id)
+    }
+
+    // part of a transfer of the given value that is burned, rounded down
+    pub fn transfer_burn(token_id: u32, value: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let rate = Self::transfer_burn_rate(token_id);
+        if rate == 0 {
+            return Ok(Zero::zero());
+        }
+        Self::charge_ppm(token_id, value, rate, Rounding::Down)
+    }
+
+    // charge of parts per million of a value of a token, rounded by the rounding policy of the token
+    // tokens without a policy keep the rounding the caller has always used for the charge
+    pub(super) fn charge_ppm(token_id: u32, value: T::TokenBalance, ppm: u32, default: Rounding) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let rounding = Self::rounding_policy(token_id).unwrap_or(default);
+        math::mul_div_rounded(value, T::TokenBalance::sa(ppm as u64), T::TokenBalance::sa(1_000_000u64), rounding)
+    }
+
+    // domain separator of the messages signed off-chain
+    // the hash of the genesis block, so that a signature for one chain cannot be replayed on another
+    pub fn signing_domain() -> T::Hash {
+        <system::Module<T>>::block_hash(T::BlockNumber::zero())
+    }
+
+    // stores the hash of what a multi-leg operation executed under a new receipt id
+    // transfer batches are receipted as (token id, legs), swaps as (old token, new token, account, value, new value)
+    // pool operations as (pool id, account) followed by the amounts in the order of their event
+    // the receipt id is returned in the event, so counterparties can prove the execution later
+    pub(super) fn store_receipt<E: Codec>(executed: &E) -> u64 {
+        let receipt_id = Self::receipt_id();
+        let hash = T::Hashing::hash_of(executed);
+        <Receipts<T>>::insert(receipt_id, hash);
+        <ReceiptId<T>>::put(receipt_id.wrapping_add(1));
+        Self::deposit_event(RawEvent::ReceiptStored(receipt_id, hash));
+        receipt_id
+    }
+
+    // whether a receipt matches the given execution
+    pub fn verify_receipt<E: Codec>(receipt_id: u64, executed: &E) -> bool {
+        Self::receipt(receipt_id) == Some(T::Hashing::hash_of(executed))
+    }
+
+    // largest lock on the balance of an account for a token
+    // locks overlap, so the locked balance is the largest lock, not their sum
+    pub fn locked_balance(token_id: u32, who: &T::AccountId) -> T::TokenBalance {
+        Self::locks((token_id, who.clone())).into_iter()
+            .fold(Zero::zero(), |locked, (_, amount)| if amount > locked { amount } else { locked })
+    }
+
+    // checks that spending the value leaves at least the locked balance
+    // a locked balance cannot be topped up from a credit line, so the check uses the balance alone
+    pub(super) fn ensure_unlocked(token_id: u32, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let locked = Self::locked_balance(token_id, who);
+        if !locked.is_zero() {
+            let balance = Self::balance_of(token_id, who);
+            ensure!(balance >= value && balance - value >= locked, "Balance is locked");
+        }
+        Ok(())
+    }
+
+    // writes the reserved balance of an account for a token, removing the entry at zero
+    // keeps the reserved supply and the holder index of the token in sync
+    pub(super) fn set_reserved_balance(token_id: u32, who: &T::AccountId, reserved: T::TokenBalance) {
+        let held = Self::holds_token(token_id, who);
+        let previous = Self::reserved_balance_of((token_id, who.clone()));
+        <ReservedSupply<T>>::mutate(token_id, |supply| {
+            *supply = math::saturating_add(math::saturating_sub(*supply, previous), reserved);
+        });
+        if reserved.is_zero() {
+            <ReservedBalanceOf<T>>::remove((token_id, who.clone()));
+        } else {
+            <ReservedBalanceOf<T>>::insert((token_id, who.clone()), reserved);
+        }
+        Self::update_holder(token_id, who, held);
+    }
+
+    // removes the state a destroyed token kept for a holder
+    // entries of accounts that no longer hold the token are left, token ids are never reused
+    pub(super) fn clear_holder_state(token_id: u32, who: &T::AccountId) {
+        let key = (token_id, who.clone());
+        <Frozen<T>>::remove(key.clone());
+        <Blacklist<T>>::remove(key.clone());
+        <Whitelist<T>>::remove(key.clone());
+        <CreditLines<T>>::remove(key.clone());
+        <Locks<T>>::remove(key.clone());
+        <Holds<T>>::remove(key.clone());
+        <LowBalanceThreshold<T>>::remove(key);
+    }
+
+    // removes the per token state of a destroyed token
+    pub(super) fn clear_token_state(token_id: u32) {
+        <Paused<T>>::remove(token_id);
+        <DeniedTokens<T>>::remove(token_id);
+        <AdminSunset<T>>::remove(token_id);
+        <FrozenMetadata<T>>::remove(token_id);
+        <MetadataManagers<T>>::remove(token_id);
+        <FeesToTreasury<T>>::remove(token_id);
+        <TransferBurnRate<T>>::remove(token_id);
+        <SwapPrograms<T>>::remove(token_id);
+        <Buyouts<T>>::remove(token_id);
+        <ReservedSupply<T>>::remove(token_id);
+        <SnapshotCursor<T>>::remove(token_id);
+        <Intents<T>>::remove(token_id);
+        <OpenHolds<T>>::remove(token_id);
+        <MintOfferCount<T>>::remove(token_id);
+        <UnclaimedCount<T>>::remove(token_id);
+        <DustThreshold<T>>::remove(token_id);
+        <RoundingPolicy<T>>::remove(token_id);
+        for kind in [LogicKind::Fee, LogicKind::Restriction, LogicKind::Reward].iter() {
+            <TokenLogic<T>>::remove((token_id, *kind));
+            <PendingLogic<T>>::remove((token_id, *kind));
+        }
+        for registry in 0..=u8::max_value() {
+            if let Some(external_id) = <ExternalAssetOfToken<T>>::take((token_id, registry)) {
+                <TokenOfExternalAsset<T>>::remove((registry, external_id));
+            }
+        }
+    }
+
+    // writes the value held under a reason, removing the hold at zero
+    pub(super) fn set_hold(token_id: u32, reason: HoldReason, who: &T::AccountId, value: T::TokenBalance) {
+        let key = (token_id, who.clone());
+        let mut holds = Self::holds(key.clone());
+        holds.retain(|(r, _)| *r != reason);
+        if !value.is_zero() {
+            holds.push((reason, value));
+        }
+        if holds.is_empty() {
+            <Holds<T>>::remove(key);
+        } else {
+            <Holds<T>>::insert(key, holds);
+        }
+    }
+
+    // whether a transfer is between two quiet system accounts and is only evented in aggregate
+    pub(super) fn is_quiet_transfer(from: &T::AccountId, to: &T::AccountId) -> bool {
+        Self::is_quiet_account(from.clone()) && Self::is_quiet_account(to.clone())
+    }
+
+    // adds a quiet transfer to the totals of the block
+    pub(super) fn record_quiet_transfer(token_id: u32, value: T::TokenBalance) {
+        <QuietTransferTotals<T>>::mutate(|totals| {
+            match totals.iter_mut().find(|(id, _, _)| *id == token_id) {
+                Some((_, count, volume)) => {
+                    *count = count.saturating_add(1);
+                    *volume = math::saturating_add(*volume, value);
+                },
+                None => totals.push((token_id, 1, value)),
+            }
+        });
+    }
+
+    // emits the aggregate event of the quiet transfers of the block
+    pub(super) fn commit_quiet_transfers() {
+        let totals = <QuietTransferTotals<T>>::take();
+        if !totals.is_empty() {
+            Self::deposit_event(RawEvent::QuietTransfers(totals));
+        }
+    }
+
+    // moves tokens held under a reason to the free balance of another account
+    // fails without writing anything if the full value is not held or cannot be received
+    pub(super) fn transfer_held(token_id: u32, reason: HoldReason, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        let held = <Self as HoldableToken<_, _>>::balance_on_hold(token_id, reason, from);
+        ensure!(held >= value, "Tokens are no longer held");
+        ensure!(Self::reserved_balance_of((token_id, from.clone())) >= value, "Held tokens are no longer reserved");
+
+        <Self as ReservableToken<_, _>>::repatriate_reserved(token_id, from, to, value)?;
+        Self::set_hold(token_id, reason, from, held - value);
+        Ok(())
+    }
+
+    // refunds an escrow to its payer and removes it
+    pub(super) fn _refund_escrow(escrow_id: u64, escrow: Escrow<T::AccountId, T::TokenBalance>) -> Result {
+        Self::_move(escrow.token_id, Self::escrow_account(escrow_id)?, escrow.payer.clone(), escrow.amount)?;
+        Self::close_escrow(escrow_id, escrow.token_id);
+
+        Self::deposit_event(RawEvent::EscrowRefunded(escrow_id, escrow.payer, escrow.amount));
+        Ok(())
+    }
+
+    // removes an escrow once its tokens have left the escrow account
+    pub(super) fn close_escrow(escrow_id: u64, token_id: u32) {
+        <Escrows<T>>::remove(escrow_id);
+        Self::close_hold(token_id);
+    }
+
+    // refunds a hashed timelock to its sender and removes it
+    pub(super) fn _refund_htlc(hash: H256, htlc: Htlc<T::AccountId, T::TokenBalance, T::BlockNumber>) {
+        let missing = <Self as HoldableToken<_, _>>::release(htlc.token_id, HTLC_HOLD, &htlc.from, htlc.amount);
+        <Htlcs<T>>::remove(hash);
+        Self::close_hold(htlc.token_id);
+
+        Self::deposit_event(RawEvent::HtlcRefunded(hash, htlc.from, htlc.amount - missing));
+    }
+
+    // counts an escrow or hashed timelock of a token as closed
+    pub(super) fn close_hold(token_id: u32) {
+        <OpenHolds<T>>::mutate(token_id, |count| *count = count.saturating_sub(1));
+    }
+
+    // removes a pending mint offer
+    pub(super) fn remove_mint_offer(offer_id: u64, token_id: u32) {
+        <MintOffers<T>>::remove(offer_id);
+        <MintOfferCount<T>>::mutate(token_id, |count| *count = count.saturating_sub(1));
+    }
+
+    // native balance paid for an amount of tokens at the price of a sale
+    // the price is quoted per whole token, so the cost of a fraction of a token rounds up
+    pub(super) fn sale_cost(token_id: u32, price: T::Balance, amount: T::TokenBalance) -> rstd::result::Result<T::Balance, &'static str> {
+        let unit = math::unit(Self::metadata(token_id).decimals)?;
+        math::mul_div_up(price, math::convert(amount)?, unit)
+    }
+
+    // opens a crowdsale of a token owned by the sender
+    // the cap is taken from the owner's balance and held until the sale is finalised
+    pub(super) fn _start_sale(sender: T::AccountId, token_id: u32, price: T::Balance, cap: T::TokenBalance, soft_cap: T::TokenBalance, start: T::BlockNumber, end: T::BlockNumber) -> Result {
+        Self::ensure_owner(token_id, &sender)?;
+        ensure!(!<Sales<T>>::exists(token_id), "Token already has a sale");
+        ensure!(!price.is_zero(), "Sale price cannot be zero");
+        ensure!(!cap.is_zero(), "Sale cap cannot be zero");
+        ensure!(soft_cap <= cap, "Soft cap cannot exceed the cap");
+        ensure!(start >= <system::Module<T>>::block_number(), "Sale cannot start in the past");
+        ensure!(end > start, "Sale must end after it starts");
+        // the cost of the whole cap must be representable
+        Self::sale_cost(token_id, price, cap)?;
+
+        <Self as HoldableToken<_, _>>::hold(token_id, SALE_HOLD, &sender, cap)?;
+        let sale = Sale {
+            issuer: sender.clone(),
+            price,
+            cap,
+            soft_cap,
+            start: start.clone(),
+            end: end.clone(),
+            sold: Zero::zero(),
+            raised: Zero::zero(),
+            buyers: 0,
+            finalised: false,
+        };
+        <Sales<T>>::insert(token_id, sale);
+
+        Self::deposit_event(RawEvent::SaleStarted(token_id, sender, price, cap, soft_cap, start, end));
+
+        Ok(())
+    }
+
+    // places the order of a buyer in a fair launch
+    // orders may exceed the cap, the whole cost is reserved until the buyer claims
+    // the order takes the positions after the orders placed before it
+    pub(super) fn place_fair_order(
+        token_id: u32,
+        buyer: T::AccountId,
+        mut sale: Sale<T::AccountId, T::TokenBalance, T::Balance, T::BlockNumber>,
+        mut launch: FairLaunch<T::TokenBalance>,
+        amount: T::TokenBalance
+    ) -> Result {
+        let key = (token_id, buyer.clone());
+        ensure!(!<FairOrders<T>>::exists(key.clone()), "Account already has an order in this fair launch");
+        let ordered = math::checked_add(launch.ordered, amount, "overflow in calculating ordered tokens")?;
+        let cost = Self::sale_cost(token_id, sale.price, amount)?;
+        let raised = sale.raised.checked_add(&cost).ok_or("overflow in calculating raised balance")?;
+        ensure!(<balances::Module<T>>::free_balance(&buyer) >= cost, "Not enough balance to pay for the purchase");
+        let account = Self::sale_account(token_id)?;
+
+        <balances::Module<T>>::make_transfer(&buyer, &account, cost)?;
+        <balances::Module<T>>::reserve(&account, cost)?;
+
+        <FairOrders<T>>::insert(key.clone(), (launch.ordered, amount));
+        <Purchases<T>>::insert(key, (amount, cost));
+        launch.ordered = ordered;
+        <FairLaunches<T>>::insert(token_id, launch);
+        sale.buyers = sale.buyers.saturating_add(1);
+        sale.sold = ordered.min(sale.cap);
+        sale.raised = raised;
+        <Sales<T>>::insert(token_id, sale);
+
+        Self::deposit_event(RawEvent::Purchased(token_id, buyer, amount, cost));
+        Ok(())
+    }
+
+    // draws the position of the first filled unit among the orders of an oversubscribed fair launch
+    // the random seed of the block is hashed with the token id, so launches finalised together draw apart
+    pub(super) fn draw_fair_launch(token_id: u32, ordered: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let seed = runtime_io::blake2_256(&(<system::Module<T>>::random_seed(), token_id).encode());
+        math::draw_below(&seed, ordered)
+    }
+
+    // tokens filled of a fair launch order, given as the position of its first unit and its amount
+    // the filled units are the cap units from the drawn position on, wrapping around after the last order,
+    // so every ordered unit has the same chance to be filled
+    // every order is filled in full if the launch was not oversubscribed
+    pub(super) fn fair_fill(launch: &FairLaunch<T::TokenBalance>, cap: T::TokenBalance, position: T::TokenBalance, amount: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let draw = match launch.draw {
+            Some(draw) => draw,
+            None => return Ok(amount),
+        };
+        let end = math::checked_add(position, amount, "overflow in calculating order end")?;
+        let fill_end = math::checked_add(draw, cap, "overflow in calculating fill end")?;
+        let overlap = |start: T::TokenBalance, stop: T::TokenBalance| end.min(stop).saturating_sub(position.max(start));
+        if fill_end <= launch.ordered {
+            Ok(overlap(draw, fill_end))
+        } else {
+            Ok(math::saturating_add(overlap(draw, launch.ordered), overlap(Zero::zero(), fill_end - launch.ordered)))
+        }
+    }
+
+    // delivers the filled part of a fair launch order and refunds the payment for the rest
+    // the payment for the filled part goes to the issuer
+    // checks everything that can fail before moving anything
+    pub(super) fn settle_fair_order(
+        token_id: u32,
+        sale: &Sale<T::AccountId, T::TokenBalance, T::Balance, T::BlockNumber>,
+        buyer: &T::AccountId,
+        position: T::TokenBalance,
+        amount: T::TokenBalance,
+        paid: T::Balance
+    ) -> Result {
+        let launch = Self::fair_launch(token_id).ok_or("Sale is not a fair launch")?;
+        let filled = Self::fair_fill(&launch, sale.cap, position, amount)?;
+        let cost = if filled == amount { paid } else { Self::sale_cost(token_id, sale.price, filled)? };
+        let refund = paid.checked_sub(&cost).ok_or("overflow in calculating refund")?;
+        if !cost.is_zero() {
+            ensure!(!<balances::Module<T>>::total_balance(&sale.issuer).is_zero(), "Issuer account does not exist");
+        }
+        let account = Self::sale_account(token_id)?;
+
+        if !filled.is_zero() {
+            Self::transfer_held(token_id, SALE_HOLD, &sale.issuer, buyer, filled)?;
+            Self::deposit_event(RawEvent::SaleTokensClaimed(token_id, buyer.clone(), filled));
+        }
+        if !cost.is_zero() {
+            let _ = <balances::Module<T>>::repatriate_reserved(&account, &sale.issuer, cost)?;
+        }
+        if !refund.is_zero() {
+            let missing = <balances::Module<T>>::repatriate_reserved(&account, buyer, refund)?;
+            Self::deposit_event(RawEvent::SaleRefunded(token_id, buyer.clone(), refund - missing));
+        }
+        Ok(())
+    }
+
+    // writes a sale, removing it once it is finalised and every buyer has claimed
+    pub(super) fn store_or_close_sale(token_id: u32, sale: Sale<T::AccountId, T::TokenBalance, T::Balance, T::BlockNumber>) {
+        if sale.finalised && sale.buyers == 0 {
+            <Sales<T>>::remove(token_id);
+            <FairLaunches<T>>::remove(token_id);
+        } else {
+            <Sales<T>>::insert(token_id, sale);
+        }
+    }
+
+    // native balance for moving the curve supply of a token from supply to supply + amount
+    // buys round up and sells round down, so that the reserve always covers the issued tokens
+    // the exponential curve is summed step by step, bounded by the max batch size
+    // the curve prices are quoted per whole token, so the cost is scaled down by the decimals of the token
+    pub(super) fn curve_cost(token_id: u32, curve: &BondingCurve<T::TokenBalance, T::Balance>, supply: T::TokenBalance, amount: T::TokenBalance, round_up: bool) -> rstd::result::Result<T::Balance, &'static str> {
+        let cost = match *curve {
+            BondingCurve::Linear(base, numerator, denominator) => {
+                // integral of the price between supply and supply + amount
+                // base * amount + numerator * amount * (2 * supply + amount) / (2 * denominator)
+                let start: T::Balance = math::convert(supply)?;
+                let amount: T::Balance = math::convert(amount)?;
+                let span = start.checked_add(&start)
+                    .and_then(|double| double.checked_add(&amount))
+                    .and_then(|sum| sum.checked_mul(&amount))
+                    .ok_or("overflow in calculating curve cost")?;
+                let divisor = denominator.checked_add(&denominator).ok_or("overflow in calculating curve cost")?;
+                let slope_cost = if round_up {
+                    math::mul_div_up(numerator, span, divisor)?
+                } else {
+                    math::mul_div(numerator, span, divisor)?
+                };
+                base.checked_mul(&amount)
+                    .and_then(|base_cost| base_cost.checked_add(&slope_cost))
+                    .ok_or("overflow in calculating curve cost")?
+            },
+            BondingCurve::Exponential(base, growth, step) => {
+                let end = math::checked_add(supply, amount, "overflow in calculating curve supply")?;
+                let mut position = supply;
+                let mut cost: T::Balance = Zero::zero();
+                let mut steps = 0u32;
+                while position < end {
+                    steps += 1;
+                    ensure!(steps <= Self::max_batch_size(), "Trade crosses too many price steps");
+                    let index = position / step;
+                    let step_end = math::checked_add(index * step, step, "overflow in calculating curve supply")?;
+                    let step_end = if step_end < end { step_end } else { end };
+                    let price = math::compound_ppm(base, growth, math::convert(index)?)?;
+                    let step_cost = price.checked_mul(&math::convert(step_end - position)?).ok_or("overflow in calculating curve cost")?;
+                    cost = cost.checked_add(&step_cost).ok_or("overflow in calculating curve cost")?;
+                    position = step_end;
+                }
+                cost
+            },
+        };
+
+        let unit = math::unit(Self::metadata(token_id).decimals)?;
+        if round_up {
+            math::mul_div_up(cost, T::Balance::sa(1), unit)
+        } else {
+            math::mul_div(cost, T::Balance::sa(1), unit)
+        }
+    }
+
+    // writes the name and ticker of a token, keeping the ticker registry in sync
+    // the same length, uniqueness and reserved prefix checks as in init apply to the new ticker
+    pub(super) fn write_name_and_ticker(token_id: u32, name: Vec<u8>, ticker: Vec<u8>) -> Result {
+        Self::ensure_metadata_length(&name, &ticker)?;
+
+        let mut metadata = Self::metadata(token_id);
+        let old_ticker = Self::normalize_ticker(&metadata.ticker);
+        let new_ticker = Self::normalize_ticker(&ticker);
+        if new_ticker != old_ticker {
+            ensure!(!<TickerRegistry<T>>::exists(new_ticker.clone()), "Ticker is already registered");
+            Self::ensure_ticker_not_reserved(&ticker)?;
+            <TickerRegistry<T>>::remove(old_ticker);
+            <TickerRegistry<T>>::insert(new_ticker, token_id);
+        }
+
+        metadata.name = name.clone();
+        metadata.ticker = ticker.clone();
+        let metadata_hash = metadata.metadata_hash;
+        <Metadata<T>>::insert(token_id, metadata);
+
+        Self::deposit_event(RawEvent::MetadataUpdated(token_id, name, ticker, metadata_hash));
+        Ok(())
+    }
+
+    // records the issuer deposit reserved by charge_creation_fee against a new token
+    // the deposit is returned when the token is destroyed
+    pub(super) fn record_issuer_deposit(token_id: u32, issuer: &T::AccountId) {
+        let deposit = Self::issuer_deposit();
+        if !deposit.is_zero() {
+            <IssuerDeposits<T>>::insert(token_id, (issuer.clone(), deposit));
+        }
+    }
+
+    // account holding the reserves of a pool
+    // derived by hashing the pool id, so nobody holds its key
+    pub(super) fn pool_account(pool_id: u32) -> rstd::result::Result<T::AccountId, &'static str> {
+        let seed = runtime_io::blake2_256(&(b"erc20:pool", pool_id).encode());
+        T::AccountId::decode(&mut &seed[..]).ok_or("Pool account cannot be derived")
+    }
+
+    // account holding the native reserve of the bonding curve of a token
+    // derived by hashing the token id like pool_account, so the reserve is apart from any user balance
+    pub(super) fn curve_account(token_id: u32) -> rstd::result::Result<T::AccountId, &'static str> {
+        let seed = runtime_io::blake2_256(&(b"erc20:curve", token_id).encode());
+        T::AccountId::decode(&mut &seed[..]).ok_or("Curve account cannot be derived")
+    }
+
+    // account holding the tokens parked in an escrow
+    // derived by hashing the escrow id like pool_account, so every escrow has its own balance
+    pub(super) fn escrow_account(escrow_id: u64) -> rstd::result::Result<T::AccountId, &'static str> {
+        let seed = runtime_io::blake2_256(&(b"erc20:escrow", escrow_id).encode());
+        T::AccountId::decode(&mut &seed[..]).ok_or("Escrow account cannot be derived")
+    }
+
+    // account holding the payments of the crowdsales of a token
+    // derived by hashing the token id like pool_account, so payments are apart from the issuer's balance
+    pub(super) fn sale_account(token_id: u32) -> rstd::result::Result<T::AccountId, &'static str> {
+        let seed = runtime_io::blake2_256(&(b"erc20:sale", token_id).encode());
+        T::AccountId::decode(&mut &seed[..]).ok_or("Sale account cannot be derived")
+    }
+
+    // name and ticker of the lp token of a pool
+    // the ticker has the lp prefix, which users cannot register
+    pub(super) fn lp_token_metadata(pool_id: u32) -> (Vec<u8>, Vec<u8>) {
+        let mut name = b"Liquidity Pool ".to_vec();
+        name.extend_from_slice(&Self::decimal_bytes(pool_id as usize));
+        let mut ticker = b"LP-".to_vec();
+        ticker.extend_from_slice(&Self::decimal_bytes(pool_id as usize));
+        (name, ticker)
+    }
+
+    // amount of token_out a pool pays for amount_in of token_in, keeping the product of the reserves
+    // the swap fee is taken from the input and stays in the pool, rounded up unless the input token has a rounding policy
+    pub(super) fn pool_swap_out(pool: &Pool<T::AccountId>, token_in: u32, token_out: u32, amount_in: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let reserve_in = Self::balance_of(token_in, &pool.account);
+        let reserve_out = Self::balance_of(token_out, &pool.account);
+        ensure!(!reserve_in.is_zero() && !reserve_out.is_zero(), "Pool has no liquidity");
+        let fee = Self::charge_ppm(token_in, amount_in, Self::swap_fee().min(1_000_000), Rounding::Up)?;
+        let effective_in = math::checked_sub(amount_in, fee, "overflow in calculating swap fee")?;
+        let denominator = math::checked_add(reserve_in, effective_in, "overflow in calculating pool reserve")?;
+        math::mul_div(reserve_out, effective_in, denominator)
+    }
+
+    // price of a pool in units of token b per whole token a, from its reserves
+    // zero while the pool has no liquidity
+    pub(super) fn pool_price(pool: &Pool<T::AccountId>) -> T::TokenBalance {
+        let reserve_a = Self::balance_of(pool.token_a, &pool.account);
+        let reserve_b = Self::balance_of(pool.token_b, &pool.account);
+        math::unit(Self::metadata(pool.token_a).decimals)
+            .and_then(|unit| math::mul_div(reserve_b, unit, reserve_a))
+            .unwrap_or_else(|_| Zero::zero())
+    }
+
+    // period of pool price candles the current block is in
+    fn candle_period_now() -> u64 {
+        <system::Module<T>>::block_number().as_() / (Self::candle_period().max(1) as u64)
+    }
+
+    // adds a swap that moved the price of a pool from open to close to the candle of the current period
+    // the candles are a ring buffer of max candles slots, a new period replaces the candle in its slot
+    pub(super) fn record_candle(pool_id: u32, open: T::TokenBalance, close: T::TokenBalance, volume: T::TokenBalance) {
+        let slots = Self::max_candles();
+        if slots == 0 {
+            return;
+        }
+        let period = Self::candle_period_now();
+        let key = (pool_id, (period % slots as u64) as u32);
+        let candle = match Self::candle(key) {
+            Some(mut candle) if candle.period == period => {
+                candle.high = candle.high.max(close);
+                candle.low = candle.low.min(close);
+                candle.close = close;
+                candle.volume = math::saturating_add(candle.volume, volume);
+                candle
+            },
+            _ => Candle {
+                period,
+                open,
+                high: open.max(close),
+                low: open.min(close),
+                close,
+                volume,
+            },
+        };
+        <Candles<T>>::insert(key, candle);
+    }
+
+    // price candles of a pool over the last max candles periods, oldest first
+    // periods without swaps have no candle
+    pub fn candles(pool_id: u32) -> Vec<Candle<T::TokenBalance>> {
+        let slots = Self::max_candles();
+        let oldest = Self::candle_period_now().saturating_add(1).saturating_sub(slots as u64);
+        let mut candles: Vec<_> = (0..slots)
+            .filter_map(|slot| Self::candle((pool_id, slot)))
+            .filter(|candle| candle.period >= oldest)
+            .collect();
+        candles.sort_by_key(|candle| candle.period);
+        candles
+    }
+
+    // plans one leg of a pool operation on a ledger, so that no leg fails after another has moved
+    // applies the same restrictions as a transfer, but no fee, burn, credit line or logic module
+    // the leg is written when the returned ledger is committed
+    pub(super) fn plan_pool_leg(token_id: u32, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> rstd::result::Result<Ledger<T>, &'static str> {
+        let mut ledger = Ledger::new(token_id);
+        ledger.move_restricted(from, to, value)?;
+        Ok(ledger)
+    }
+}
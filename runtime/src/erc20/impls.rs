@@ -0,0 +1,272 @@
+// traits exposed by the erc20 module to other runtime modules
+// and their implementations
+
+use super::*;
+
+// origin check which only passes for the root origin
+// can be used as the ForceOrigin of a runtime
+pub struct EnsureRoot<AccountId>(rstd::marker::PhantomData<AccountId>);
+
+impl<O: Into<Option<system::RawOrigin<AccountId>>>, AccountId> EnsureOrigin<O> for EnsureRoot<AccountId> {
+    type Success = ();
+
+    fn ensure_origin(o: O) -> rstd::result::Result<Self::Success, &'static str> {
+        ensure_root(o)
+    }
+}
+
+// hook for other modules to react to token transfers
+// called with the token id, sender, receiver and value once balances are updated
+pub trait OnTokenTransfer<AccountId, Balance> {
+    fn on_transfer(token_id: u32, from: &AccountId, to: &AccountId, value: Balance);
+}
+
+impl<AccountId, Balance> OnTokenTransfer<AccountId, Balance> for () {
+    fn on_transfer(_token_id: u32, _from: &AccountId, _to: &AccountId, _value: Balance) {}
+}
+
+// compliance check for transfers, e.g. backed by an identity or kyc module
+// both checks must pass before any tokens are moved
+pub trait CanTransfer<AccountId> {
+    // whether the account is allowed to send the token
+    fn can_send(token_id: u32, who: &AccountId) -> bool;
+    // whether the account is allowed to receive the token
+    fn can_receive(token_id: u32, who: &AccountId) -> bool;
+}
+
+impl<AccountId> CanTransfer<AccountId> for () {
+    fn can_send(_token_id: u32, _who: &AccountId) -> bool { true }
+    fn can_receive(_token_id: u32, _who: &AccountId) -> bool { true }
+}
+
+// interface for other runtime modules to move tokens without dispatching extrinsics
+// all functions are keyed by the token id
+pub trait MultiTokenCurrency<AccountId> {
+    type Balance;
+
+    // the balance of an account for a token
+    fn free_balance(token_id: u32, who: &AccountId) -> Self::Balance;
+    // transfers tokens from one account to another
+    fn transfer(token_id: u32, from: &AccountId, to: &AccountId, value: Self::Balance) -> Result;
+    // mints new tokens to an account, increasing the total supply
+    fn deposit(token_id: u32, who: &AccountId, value: Self::Balance) -> Result;
+    // burns tokens from an account, decreasing the total supply
+    fn withdraw(token_id: u32, who: &AccountId, value: Self::Balance) -> Result;
+}
+
+// membership check for accounts
+// lets other modules gate features without knowing how membership is decided
+pub trait Contains<AccountId> {
+    fn contains(who: &AccountId) -> bool;
+}
+
+// token and minimum balance an account must hold to be contained in TokenHolders
+pub trait HoldingRequirement<Balance> {
+    fn token_id() -> u32;
+    fn min_balance() -> Balance;
+}
+
+// contains every account holding at least the required balance of a token
+// e.g. members-only features for holders of a community token
+pub struct TokenHolders<T, R>(rstd::marker::PhantomData<(T, R)>);
+
+impl<T: Trait, R: HoldingRequirement<T::TokenBalance>> Contains<T::AccountId> for TokenHolders<T, R> {
+    fn contains(who: &T::AccountId) -> bool {
+        <Module<T>>::holds_at_least(R::token_id(), who, R::min_balance())
+    }
+}
+
+impl<T: Trait> MultiTokenCurrency<T::AccountId> for Module<T> {
+    type Balance = T::TokenBalance;
+
+    fn free_balance(token_id: u32, who: &T::AccountId) -> Self::Balance {
+        Self::balance_of(token_id, who)
+    }
+
+    fn transfer(token_id: u32, from: &T::AccountId, to: &T::AccountId, value: Self::Balance) -> Result {
+        Self::_transfer(token_id, from.clone(), to.clone(), value)
+    }
+
+    fn deposit(token_id: u32, who: &T::AccountId, value: Self::Balance) -> Result {
+        Self::_mint(token_id, who.clone(), value)
+    }
+
+    fn withdraw(token_id: u32, who: &T::AccountId, value: Self::Balance) -> Result {
+        Self::_burn(token_id, who.clone(), value)
+    }
+}
+
+// logic modules provided by the runtime, selected per token by kind and id
+// lets token owners switch token behaviour between the policies the runtime ships
+pub trait LogicModules<AccountId, Balance> {
+    // whether the runtime provides a logic module of the kind with the id
+    fn exists(kind: LogicKind, id: u32) -> bool;
+    // fee charged to the sender on top of the transferred value
+    fn fee(id: u32, token_id: u32, from: &AccountId, to: &AccountId, value: Balance) -> Balance;
+    // whether the transfer is allowed
+    fn can_transfer(id: u32, token_id: u32, from: &AccountId, to: &AccountId, value: Balance) -> bool;
+    // called once a transfer is done
+    fn on_transfer(id: u32, token_id: u32, from: &AccountId, to: &AccountId, value: Balance);
+}
+
+impl<AccountId, Balance: Zero> LogicModules<AccountId, Balance> for () {
+    fn exists(_kind: LogicKind, _id: u32) -> bool { false }
+    fn fee(_id: u32, _token_id: u32, _from: &AccountId, _to: &AccountId, _value: Balance) -> Balance { Zero::zero() }
+    fn can_transfer(_id: u32, _token_id: u32, _from: &AccountId, _to: &AccountId, _value: Balance) -> bool { true }
+    fn on_transfer(_id: u32, _token_id: u32, _from: &AccountId, _to: &AccountId, _value: Balance) {}
+}
+
+// treasury account of the chain that transfer fees can be directed to
+pub trait FeeDestination<AccountId> {
+    // none if the runtime has no treasury
+    fn treasury() -> Option<AccountId>;
+}
+
+impl<AccountId> FeeDestination<AccountId> for () {
+    fn treasury() -> Option<AccountId> { None }
+}
+
+// locks on token balances for other modules, e.g. staking or governance
+// locked tokens stay in the account of the holder but cannot be spent
+pub trait LockableToken<AccountId, Balance> {
+    // creates or replaces the lock with the id
+    fn set_lock(token_id: u32, id: LockIdentifier, who: &AccountId, amount: Balance);
+    fn remove_lock(token_id: u32, id: LockIdentifier, who: &AccountId);
+}
+
+impl<T: Trait> LockableToken<T::AccountId, T::TokenBalance> for Module<T> {
+    fn set_lock(token_id: u32, id: LockIdentifier, who: &T::AccountId, amount: T::TokenBalance) {
+        <Locks<T>>::mutate((token_id, who.clone()), |locks| {
+            locks.retain(|(lock_id, _)| *lock_id != id);
+            locks.push((id, amount));
+        });
+        Self::deposit_event(RawEvent::LockSet(token_id, id, who.clone(), amount));
+    }
+
+    fn remove_lock(token_id: u32, id: LockIdentifier, who: &T::AccountId) {
+        let key = (token_id, who.clone());
+        let mut locks = Self::locks(key.clone());
+        locks.retain(|(lock_id, _)| *lock_id != id);
+        if locks.is_empty() {
+            <Locks<T>>::remove(key);
+        } else {
+            <Locks<T>>::insert(key, locks);
+        }
+        Self::deposit_event(RawEvent::LockRemoved(token_id, id, who.clone()));
+    }
+}
+
+// reserved (held) token balances for other modules, e.g. collateral
+// reserved tokens are taken out of the free balance, so they cannot be transferred,
+// but still count towards the total supply
+pub trait ReservableToken<AccountId, Balance> {
+    fn reserved_balance(token_id: u32, who: &AccountId) -> Balance;
+    // moves the value from the free to the reserved balance
+    fn reserve(token_id: u32, who: &AccountId, value: Balance) -> Result;
+    // moves up to the value back to the free balance, returns what could not be unreserved
+    fn unreserve(token_id: u32, who: &AccountId, value: Balance) -> Balance;
+    // burns up to the value of the reserved balance, returns what could not be slashed
+    fn slash_reserved(token_id: u32, who: &AccountId, value: Balance) -> Balance;
+    // moves up to the value of the reserved balance to the free balance of the beneficiary
+    // returns what could not be moved
+    fn repatriate_reserved(token_id: u32, slashed: &AccountId, beneficiary: &AccountId, value: Balance) -> rstd::result::Result<Balance, &'static str>;
+}
+
+impl<T: Trait> ReservableToken<T::AccountId, T::TokenBalance> for Module<T> {
+    fn reserved_balance(token_id: u32, who: &T::AccountId) -> T::TokenBalance {
+        Self::reserved_balance_of((token_id, who.clone()))
+    }
+
+    fn reserve(token_id: u32, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let balance = Self::balance_of(token_id, who);
+        ensure!(balance >= value, "Not enough balance.");
+        Self::ensure_unlocked(token_id, who, value)?;
+        let reserved = math::checked_add(Self::reserved_balance_of((token_id, who.clone())), value, "overflow in calculating reserved balance")?;
+
+        // the reserved balance is written first, so that reserving the whole balance keeps the account a holder
+        Self::set_reserved_balance(token_id, who, reserved);
+        Self::set_balance(token_id, who, balance - value);
+
+        Self::deposit_event(RawEvent::Reserved(token_id, who.clone(), value));
+        Ok(())
+    }
+
+    fn unreserve(token_id: u32, who: &T::AccountId, value: T::TokenBalance) -> T::TokenBalance {
+        let reserved = Self::reserved_balance_of((token_id, who.clone()));
+        let actual = if value < reserved { value } else { reserved };
+        let balance = Self::balance_of(token_id, who);
+
+        Self::set_balance(token_id, who, math::saturating_add(balance, actual));
+        Self::set_reserved_balance(token_id, who, reserved - actual);
+
+        Self::deposit_event(RawEvent::Unreserved(token_id, who.clone(), actual));
+        value - actual
+    }
+
+    fn slash_reserved(token_id: u32, who: &T::AccountId, value: T::TokenBalance) -> T::TokenBalance {
+        let reserved = Self::reserved_balance_of((token_id, who.clone()));
+        let actual = if value < reserved { value } else { reserved };
+
+        Self::set_reserved_balance(token_id, who, reserved - actual);
+        let mut token = Self::token_details(token_id);
+        token.total_supply = math::saturating_sub(token.total_supply, actual);
+        <Tokens<T>>::insert(token_id, token);
+
+        Self::deposit_event(RawEvent::ReservedSlashed(token_id, who.clone(), actual));
+        value - actual
+    }
+
+    fn repatriate_reserved(token_id: u32, slashed: &T::AccountId, beneficiary: &T::AccountId, value: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let reserved = Self::reserved_balance_of((token_id, slashed.clone()));
+        let actual = if value < reserved { value } else { reserved };
+        let balance = Self::balance_of(token_id, beneficiary);
+        let updated_balance = math::checked_add(balance, actual, "overflow in calculating balance")?;
+        Self::ensure_can_hold(token_id, beneficiary)?;
+
+        Self::set_reserved_balance(token_id, slashed, reserved - actual);
+        Self::set_balance(token_id, beneficiary, updated_balance);
+
+        Self::deposit_event(RawEvent::ReserveRepatriated(token_id, slashed.clone(), beneficiary.clone(), actual));
+        Ok(value - actual)
+    }
+}
+
+// named holds on token balances, built on reserved balances
+// each module holds under its own reason, so holds of different modules on the same
+// account do not clobber each other and can be audited individually
+pub trait HoldableToken<AccountId, Balance> {
+    fn balance_on_hold(token_id: u32, reason: HoldReason, who: &AccountId) -> Balance;
+    // reserves the value under the reason
+    fn hold(token_id: u32, reason: HoldReason, who: &AccountId, value: Balance) -> Result;
+    // unreserves up to the value held under the reason, returns what could not be released
+    fn release(token_id: u32, reason: HoldReason, who: &AccountId, value: Balance) -> Balance;
+}
+
+impl<T: Trait> HoldableToken<T::AccountId, T::TokenBalance> for Module<T> {
+    fn balance_on_hold(token_id: u32, reason: HoldReason, who: &T::AccountId) -> T::TokenBalance {
+        Self::holds((token_id, who.clone())).into_iter()
+            .find(|(r, _)| *r == reason)
+            .map(|(_, value)| value)
+            .unwrap_or_else(Zero::zero)
+    }
+
+    fn hold(token_id: u32, reason: HoldReason, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let held = math::checked_add(Self::balance_on_hold(token_id, reason, who), value, "overflow in calculating held balance")?;
+        <Self as ReservableToken<_, _>>::reserve(token_id, who, value)?;
+        Self::set_hold(token_id, reason, who, held);
+
+        Self::deposit_event(RawEvent::Held(token_id, reason, who.clone(), value));
+        Ok(())
+    }
+
+    fn release(token_id: u32, reason: HoldReason, who: &T::AccountId, value: T::TokenBalance) -> T::TokenBalance {
+        let held = Self::balance_on_hold(token_id, reason, who);
+        let actual = if value < held { value } else { held };
+        // slashing the reserved balance directly can leave less reserved than held
+        let _ = <Self as ReservableToken<_, _>>::unreserve(token_id, who, actual);
+        Self::set_hold(token_id, reason, who, held - actual);
+
+        Self::deposit_event(RawEvent::Released(token_id, reason, who.clone(), actual));
+        value - actual
+    }
+}
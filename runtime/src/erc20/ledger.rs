@@ -0,0 +1,287 @@
+// in-memory balances of the accounts touched by the legs of an operation on a token
+// there is no transactional storage, so every leg is checked and applied on the ledger first
+// and the ledger is only written once all legs of the operation have passed
+// a failing leg therefore leaves nothing behind, whatever leg it is
+
+use super::*;
+
+pub(super) struct Ledger<T: Trait> {
+    token_id: u32,
+    min_balance: T::TokenBalance,
+    balances: BTreeMap<T::AccountId, T::TokenBalance>,
+    // credit lines changed by the legs
+    credit_lines: BTreeMap<T::AccountId, CreditLine<T::AccountId, T::TokenBalance>>,
+    // balance removed from the supply by burns and dust
+    removed: T::TokenBalance,
+    // legs to notify the transfer handler of, and whether they are part of the transfer root
+    moves: Vec<(T::AccountId, T::AccountId, T::TokenBalance, bool)>,
+    // transfers between quiet accounts, added to the totals of the block
+    quiet: Vec<T::TokenBalance>,
+    // reward logic module calls, made once the ledger is written
+    rewards: Vec<(u32, T::AccountId, T::AccountId, T::TokenBalance)>,
+    events: Vec<Event<T>>,
+}
+
+impl<T: Trait> Ledger<T> {
+    pub(super) fn new(token_id: u32) -> Self {
+        Ledger {
+            token_id,
+            min_balance: <Module<T>>::min_balance(token_id),
+            balances: BTreeMap::new(),
+            credit_lines: BTreeMap::new(),
+            removed: Zero::zero(),
+            moves: Vec::new(),
+            quiet: Vec::new(),
+            rewards: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    // balance of an account after the legs applied so far
+    pub(super) fn balance(&mut self, who: &T::AccountId) -> T::TokenBalance {
+        let token_id = self.token_id;
+        *self.balances.entry(who.clone()).or_insert_with(|| <Module<T>>::balance_of(token_id, who))
+    }
+
+    // credit line of a borrower after the legs applied so far
+    fn credit_line(&mut self, who: &T::AccountId) -> Option<CreditLine<T::AccountId, T::TokenBalance>> {
+        if let Some(line) = self.credit_lines.get(who) {
+            return Some(line.clone());
+        }
+        <Module<T>>::credit_line((self.token_id, who.clone()))
+    }
+
+    // tops the balance of a borrower up to the given value out of its credit line
+    // does nothing if the balance already covers the value or the account has no credit line
+    fn draw_credit(&mut self, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let balance = self.balance(who);
+        if balance >= value {
+            return Ok(());
+        }
+        let mut line = match self.credit_line(who) {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+
+        let shortfall = value - balance;
+        let debt = math::checked_add(line.debt, shortfall, "overflow in calculating debt")?;
+        ensure!(debt <= line.limit, "Transfer exceeds the credit line");
+        // the grantor leg is held to the same rules as a transfer from the grantor
+        <Module<T>>::ensure_can_transfer(self.token_id, &line.grantor, who)?;
+
+        self.move_balance(&line.grantor, who, shortfall)?;
+        line.debt = debt;
+        self.events.push(RawEvent::CreditDrawn(self.token_id, who.clone(), line.grantor.clone(), shortfall));
+        self.credit_lines.insert(who.clone(), line);
+        Ok(())
+    }
+
+    // repays the debt of a borrower to its grantor out of its unlocked balance
+    // a repayment that cannot be made is left to a later transfer instead of failing this one
+    fn repay_credit(&mut self, who: &T::AccountId) -> Result {
+        let mut line = match self.credit_line(who) {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+        let balance = self.balance(who);
+        let locked = <Module<T>>::locked_balance(self.token_id, who);
+        let spendable = math::saturating_sub(balance, locked);
+        let repayment = if line.debt < spendable { line.debt } else { spendable };
+        if repayment.is_zero()
+            || <Module<T>>::ensure_can_transfer(self.token_id, who, &line.grantor).is_err()
+            || self.ensure_can_credit(&line.grantor, repayment).is_err() {
+            return Ok(());
+        }
+
+        self.move_balance(who, &line.grantor, repayment)?;
+        line.debt = line.debt - repayment;
+        self.events.push(RawEvent::CreditRepaid(self.token_id, who.clone(), line.grantor.clone(), repayment));
+        self.credit_lines.insert(who.clone(), line);
+        Ok(())
+    }
+
+    // queues an event, deposited once the ledger is written
+    pub(super) fn deposit_event(&mut self, event: Event<T>) {
+        self.events.push(event);
+    }
+
+    // checks that spending the value leaves at least the locked balance
+    pub(super) fn ensure_unlocked(&mut self, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let locked = <Module<T>>::locked_balance(self.token_id, who);
+        if !locked.is_zero() {
+            let balance = self.balance(who);
+            ensure!(balance >= value && balance - value >= locked, "Balance is locked");
+        }
+        Ok(())
+    }
+
+    // takes the value from an account, without settling the dust
+    fn take(&mut self, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let balance = self.balance(who);
+        ensure!(!balance.is_zero(), "Account does not own this token");
+        ensure!(balance >= value, "Not enough balance.");
+        self.balances.insert(who.clone(), balance - value);
+        Ok(())
+    }
+
+    // a remaining balance below the minimum balance is dust, it is removed from the supply
+    fn settle(&mut self, who: &T::AccountId) {
+        let balance = self.balance(who);
+        if !balance.is_zero() && balance < self.min_balance {
+            self.removed = math::saturating_add(self.removed, balance);
+            self.events.push(RawEvent::DustLost(self.token_id, who.clone(), balance));
+            self.balances.insert(who.clone(), Zero::zero());
+        }
+    }
+
+    // takes the value from an account and settles its dust
+    fn debit(&mut self, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        self.take(who, value)?;
+        self.settle(who);
+        Ok(())
+    }
+
+    // gives the value to an account
+    // the resulting balance must reach the minimum balance and the account must be able to hold the token
+    fn credit(&mut self, who: &T::AccountId, value: T::TokenBalance) -> Result {
+        let updated_balance = self.ensure_can_credit(who, value)?;
+        self.balances.insert(who.clone(), updated_balance);
+        Ok(())
+    }
+
+    // checks that the value can be given to an account, returning the resulting balance
+    fn ensure_can_credit(&mut self, who: &T::AccountId, value: T::TokenBalance) -> rstd::result::Result<T::TokenBalance, &'static str> {
+        let balance = self.balance(who);
+        let updated_balance = math::checked_add(balance, value, "overflow in calculating balance")?;
+        ensure!(updated_balance >= self.min_balance, "Receiver balance would be below the minimum balance");
+        <Module<T>>::ensure_can_hold(self.token_id, who)?;
+        Ok(updated_balance)
+    }
+
+    // moves tokens between two accounts with the checks of a bare move, see Module::_move
+    // even a bare move cannot spend a locked balance, as other modules rely on their locks
+    // moving to the same account changes nothing
+    pub(super) fn move_balance(&mut self, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        if from == to {
+            let balance = self.balance(from);
+            ensure!(!balance.is_zero(), "Account does not own this token");
+            ensure!(balance >= value, "Not enough balance.");
+        } else {
+            self.ensure_unlocked(from, value)?;
+            self.debit(from, value)?;
+            self.credit(to, value)?;
+        }
+        self.moves.push((from.clone(), to.clone(), value, false));
+        Ok(())
+    }
+
+    // marks the last move as part of the transfer root
+    pub(super) fn record_last_move(&mut self) {
+        if let Some(last) = self.moves.last_mut() {
+            last.3 = true;
+        }
+    }
+
+    // moves tokens with the restrictions of a transfer, but no fee, burn, credit line or logic module
+    // the move is part of the transfer root and emits a transfer event
+    pub(super) fn move_restricted(&mut self, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance) -> Result {
+        <Module<T>>::ensure_can_transfer(self.token_id, from, to)?;
+        self.move_balance(from, to, value)?;
+        self.record_last_move();
+        self.events.push(RawEvent::Transfer(self.token_id, from.clone(), to.clone(), value, None));
+        Ok(())
+    }
+
+    // applies a transfer with all the checks, fees and burns of Module::_transfer_with_context
+    pub(super) fn transfer(&mut self, from: &T::AccountId, to: &T::AccountId, value: T::TokenBalance, context: Option<u32>) -> Result {
+        let token_id = self.token_id;
+        <Module<T>>::ensure_can_transfer(token_id, from, to)?;
+        if let Some(id) = <Module<T>>::token_logic((token_id, LogicKind::Restriction)) {
+            ensure!(T::LogicModules::can_transfer(id, token_id, from, to, value), "Transfer rejected by the restriction policy");
+        }
+
+        // the fee is charged on top of the value and paid to the fee receiver of the token
+        let fee = match (<Module<T>>::token_logic((token_id, LogicKind::Fee)), <Module<T>>::fee_receiver(token_id)) {
+            (Some(id), Some(receiver)) => Some((receiver, T::LogicModules::fee(id, token_id, from, to, value)))
+                .filter(|(_, fee)| !fee.is_zero()),
+            _ => None,
+        };
+        let total = match fee.as_ref() {
+            Some((_, fee)) => math::checked_add(value, *fee, "overflow in calculating fee")?,
+            None => value,
+        };
+        self.ensure_unlocked(from, total)?;
+        // a shortfall is drawn from the credit line of the sender, if it has one
+        self.draw_credit(from, total)?;
+        if fee.is_some() {
+            ensure!(self.balance(from) >= total, "Not enough balance to pay the fee.");
+        }
+
+        // on deflationary tokens a fraction of the value is burned instead of delivered
+        let burn = <Module<T>>::transfer_burn(token_id, value)?;
+        let delivered = math::checked_sub(value, burn, "overflow in calculating burn")?;
+
+        // the delivered value, the burn and the fee leave the sender in a single debit
+        // so that only what remains after all of them can be lost as dust
+        self.take(from, total)?;
+        self.credit(to, delivered)?;
+        self.moves.push((from.clone(), to.clone(), delivered, true));
+        // transfers between quiet system accounts are still part of the transfer root
+        if <Module<T>>::is_quiet_transfer(from, to) {
+            self.quiet.push(delivered);
+        } else {
+            self.events.push(RawEvent::Transfer(token_id, from.clone(), to.clone(), delivered, context));
+        }
+
+        if !burn.is_zero() {
+            self.removed = math::saturating_add(self.removed, burn);
+            self.events.push(RawEvent::Burned(token_id, from.clone(), burn));
+        }
+
+        if let Some((receiver, fee)) = fee {
+            self.credit(&receiver, fee)?;
+            self.moves.push((from.clone(), receiver.clone(), fee, true));
+            self.events.push(RawEvent::FeePaid(token_id, from.clone(), receiver, fee));
+        }
+        self.settle(from);
+
+        self.repay_credit(to)?;
+
+        if let Some(id) = <Module<T>>::token_logic((token_id, LogicKind::Reward)) {
+            self.rewards.push((id, from.clone(), to.clone(), value));
+        }
+        Ok(())
+    }
+
+    // writes the balances and supply, then records the legs and deposits the events
+    pub(super) fn commit(self) {
+        let token_id = self.token_id;
+        for (who, balance) in self.balances {
+            <Module<T>>::set_balance(token_id, &who, balance);
+        }
+        if !self.removed.is_zero() {
+            let mut token = <Module<T>>::token_details(token_id);
+            token.total_supply = math::saturating_sub(token.total_supply, self.removed);
+            <Tokens<T>>::insert(token_id, token);
+        }
+        for (who, line) in self.credit_lines {
+            <CreditLines<T>>::insert((token_id, who), line);
+        }
+
+        for (from, to, value, recorded) in self.moves {
+            if recorded {
+                <Module<T>>::record_transfer(token_id, &from, &to, value);
+            }
+            T::TransferHandler::on_transfer(token_id, &from, &to, value);
+        }
+        for value in self.quiet {
+            <Module<T>>::record_quiet_transfer(token_id, value);
+        }
+        for event in self.events {
+            <Module<T>>::deposit_event(event);
+        }
+        for (id, from, to, value) in self.rewards {
+            T::LogicModules::on_transfer(id, token_id, &from, &to, value);
+        }
+    }
+}
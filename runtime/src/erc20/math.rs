@@ -0,0 +1,294 @@
+// balance arithmetic helpers shared by the erc20 module
+// every balance, allowance and supply calculation goes through these
+// so that overflow and precision behaviour is defined in one place
+
+use rstd::result;
+use parity_codec::Codec;
+use primitives::U256;
+use runtime_primitives::traits::{As, CheckedAdd, CheckedSub, CheckedMul, Saturating, SimpleArithmetic, Zero};
+use super::types::Rounding;
+
+// adds two values, failing with the given error on overflow
+pub fn checked_add<B: SimpleArithmetic>(a: B, b: B, err: &'static str) -> result::Result<B, &'static str> {
+    a.checked_add(&b).ok_or(err)
+}
+
+// subtracts b from a, failing with the given error on underflow
+pub fn checked_sub<B: SimpleArithmetic>(a: B, b: B, err: &'static str) -> result::Result<B, &'static str> {
+    a.checked_sub(&b).ok_or(err)
+}
+
+// adds two values, capping the result at the maximum value of the type
+pub fn saturating_add<B: SimpleArithmetic>(a: B, b: B) -> B {
+    a.saturating_add(b)
+}
+
+// subtracts b from a, flooring the result at zero
+pub fn saturating_sub<B: SimpleArithmetic>(a: B, b: B) -> B {
+    a.saturating_sub(b)
+}
+
+// calculates a * b / c, rounding down
+// the product is calculated with a 256-bit intermediate so it cannot overflow
+// fails if c is zero or if the final result does not fit in the balance type
+pub fn mul_div<B: SimpleArithmetic + Codec + Default>(a: B, b: B, c: B) -> result::Result<B, &'static str> {
+    let c = to_u256(&c);
+    if c.is_zero() {
+        return Err("division by zero");
+    }
+    let result = to_u256(&a).checked_mul(to_u256(&b)).ok_or("overflow in calculating product")? / c;
+    from_u256(result)
+}
+
+// calculates a * b / c like mul_div, but rounds up
+pub fn mul_div_up<B: SimpleArithmetic + Codec + Default>(a: B, b: B, c: B) -> result::Result<B, &'static str> {
+    let c = to_u256(&c);
+    if c.is_zero() {
+        return Err("division by zero");
+    }
+    let product = to_u256(&a).checked_mul(to_u256(&b)).ok_or("overflow in calculating product")?;
+    let result = product / c;
+    from_u256(if (product % c).is_zero() { result } else { result + U256::one() })
+}
+
+// calculates a * b / c with the given rounding
+// half even rounds to the nearest result, and a remainder of exactly half of c to the even result
+pub fn mul_div_rounded<B: SimpleArithmetic + Codec + Default>(a: B, b: B, c: B, rounding: Rounding) -> result::Result<B, &'static str> {
+    match rounding {
+        Rounding::Down => mul_div(a, b, c),
+        Rounding::Up => mul_div_up(a, b, c),
+        Rounding::HalfEven => {
+            let c = to_u256(&c);
+            if c.is_zero() {
+                return Err("division by zero");
+            }
+            let product = to_u256(&a).checked_mul(to_u256(&b)).ok_or("overflow in calculating product")?;
+            let result = product / c;
+            let remainder = product % c;
+            let rest = c - remainder;
+            let odd = !(result & U256::one()).is_zero();
+            from_u256(if remainder > rest || (remainder == rest && odd) { result + U256::one() } else { result })
+        },
+    }
+}
+
+// calculates value * (1 + rate_ppm / 1_000_000) ^ periods, rounding down
+// the factor is calculated by repeated squaring with a 256-bit intermediate,
+// so the cost grows with the number of bits of periods, not with periods
+// fails if the factor or the final result overflows
+pub fn compound_ppm<B: SimpleArithmetic + Codec + Default>(value: B, rate_ppm: u32, periods: u64) -> result::Result<B, &'static str> {
+    let scale = U256::from(1_000_000u64);
+    let mut factor = scale;
+    let mut base = scale + U256::from(rate_ppm);
+    let mut periods = periods;
+    while periods > 0 {
+        if periods & 1 == 1 {
+            factor = factor.checked_mul(base).ok_or("overflow in compounding")? / scale;
+        }
+        periods >>= 1;
+        if periods > 0 {
+            base = base.checked_mul(base).ok_or("overflow in compounding")? / scale;
+        }
+    }
+    let result = to_u256(&value).checked_mul(factor).ok_or("overflow in compounding")? / scale;
+    from_u256(result)
+}
+
+// calculates the integer square root of a * b, rounding down
+// the product is calculated with a 256-bit intermediate so it cannot overflow
+pub fn sqrt_product<B: SimpleArithmetic + Codec + Default>(a: B, b: B) -> result::Result<B, &'static str> {
+    let product = to_u256(&a).checked_mul(to_u256(&b)).ok_or("overflow in calculating product")?;
+    // newton's method, starting from half the product rounded up so that it cannot overflow
+    let mut root = product;
+    let mut next = (product >> 1) + (product & U256::one());
+    while next < root {
+        root = next;
+        next = (root + product / root) >> 1;
+    }
+    from_u256(root)
+}
+
+// reduces a 256-bit random seed to a value below the bound
+// the modulo bias is negligible for bounds far below 2^256, which every balance type is
+pub fn draw_below<B: Codec + Default>(seed: &[u8; 32], bound: B) -> result::Result<B, &'static str> {
+    let bound = to_u256(&bound);
+    if bound.is_zero() {
+        return Err("division by zero");
+    }
+    from_u256(U256::from_little_endian(seed) % bound)
+}
+
+// converts a value between two balance types, e.g. a token balance to a native balance
+// fails if the value does not fit in the target type
+pub fn convert<A: Codec, B: Codec + Default>(value: A) -> result::Result<B, &'static str> {
+    from_u256(to_u256(&value))
+}
+
+// balances are fixed width unsigned integers, so their SCALE encoding is little endian
+fn to_u256<B: Codec>(value: &B) -> U256 {
+    U256::from_little_endian(&value.encode())
+}
+
+fn from_u256<B: Codec + Default>(value: U256) -> result::Result<B, &'static str> {
+    let width = B::default().encode().len();
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    if bytes[width..].iter().any(|b| *b != 0) {
+        return Err("overflow in converting result to balance");
+    }
+    B::decode(&mut &bytes[..width]).ok_or("invalid balance encoding")
+}
+
+// converts an amount between two decimal precisions
+// scaling up fails on overflow, scaling down rounds down
+// a divisor too large for the balance type is larger than any amount, so scaling down by it gives zero
+pub fn scale_decimals<B: SimpleArithmetic>(amount: B, from_decimals: u8, to_decimals: u8) -> result::Result<B, &'static str> {
+    if from_decimals == to_decimals {
+        return Ok(amount);
+    }
+
+    let difference = if to_decimals > from_decimals {
+        to_decimals - from_decimals
+    } else {
+        from_decimals - to_decimals
+    };
+    let mut factor = B::sa(1);
+    for _ in 0..difference {
+        factor = match factor.checked_mul(&B::sa(10)) {
+            Some(factor) => factor,
+            None if to_decimals < from_decimals => return Ok(B::zero()),
+            None => return Err("overflow in calculating decimal scale"),
+        };
+    }
+
+    if to_decimals > from_decimals {
+        amount.checked_mul(&factor).ok_or("overflow in scaling decimals")
+    } else {
+        Ok(amount / factor)
+    }
+}
+
+// number of smallest units in a whole token with the given decimals
+pub fn unit<B: SimpleArithmetic>(decimals: u8) -> result::Result<B, &'static str> {
+    scale_decimals(B::sa(1), 0, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_down() {
+        assert_eq!(mul_div(7u64, 3, 2), Ok(10));
+        assert_eq!(mul_div(6u64, 3, 2), Ok(9));
+        assert_eq!(mul_div(0u64, 3, 2), Ok(0));
+    }
+
+    #[test]
+    fn mul_div_uses_a_wide_intermediate() {
+        assert_eq!(mul_div(u64::max_value(), u64::max_value(), u64::max_value()), Ok(u64::max_value()));
+        assert_eq!(mul_div(u128::max_value(), 2, 4), Ok(u128::max_value() / 2));
+    }
+
+    #[test]
+    fn mul_div_fails_on_overflow_and_zero_divisor() {
+        assert_eq!(mul_div(u64::max_value(), 2, 1), Err("overflow in converting result to balance"));
+        assert_eq!(mul_div(1u64, 1, 0), Err("division by zero"));
+    }
+
+    #[test]
+    fn mul_div_up_rounds_up() {
+        assert_eq!(mul_div_up(7u64, 3, 2), Ok(11));
+        assert_eq!(mul_div_up(6u64, 3, 2), Ok(9));
+        assert_eq!(mul_div_up(0u64, 3, 2), Ok(0));
+        assert_eq!(mul_div_up(1u64, 1, 0), Err("division by zero"));
+        assert_eq!(mul_div_up(u64::max_value(), 3, 2), Err("overflow in converting result to balance"));
+    }
+
+    #[test]
+    fn mul_div_rounded_follows_the_rounding() {
+        assert_eq!(mul_div_rounded(7u64, 1, 4, Rounding::Down), Ok(1));
+        assert_eq!(mul_div_rounded(7u64, 1, 4, Rounding::Up), Ok(2));
+        assert_eq!(mul_div_rounded(7u64, 1, 4, Rounding::HalfEven), Ok(2));
+        assert_eq!(mul_div_rounded(5u64, 1, 4, Rounding::HalfEven), Ok(1));
+        assert_eq!(mul_div_rounded(8u64, 1, 4, Rounding::HalfEven), Ok(2));
+    }
+
+    #[test]
+    fn mul_div_rounded_rounds_halves_to_even() {
+        assert_eq!(mul_div_rounded(1u64, 1, 2, Rounding::HalfEven), Ok(0));
+        assert_eq!(mul_div_rounded(3u64, 1, 2, Rounding::HalfEven), Ok(2));
+        assert_eq!(mul_div_rounded(5u64, 1, 2, Rounding::HalfEven), Ok(2));
+        assert_eq!(mul_div_rounded(u64::max_value(), 1, 2, Rounding::HalfEven), Ok(1 << 63));
+        assert_eq!(mul_div_rounded(1u64, 1, 0, Rounding::HalfEven), Err("division by zero"));
+    }
+
+    #[test]
+    fn compound_ppm_compounds_per_period() {
+        assert_eq!(compound_ppm(1_000_000u64, 100_000, 0), Ok(1_000_000));
+        assert_eq!(compound_ppm(1_000_000u64, 100_000, 1), Ok(1_100_000));
+        assert_eq!(compound_ppm(1_000_000u64, 100_000, 2), Ok(1_210_000));
+        assert_eq!(compound_ppm(1_000_000u64, 0, 1_000), Ok(1_000_000));
+    }
+
+    #[test]
+    fn compound_ppm_rounds_down() {
+        assert_eq!(compound_ppm(10u64, 150_000, 1), Ok(11));
+    }
+
+    #[test]
+    fn compound_ppm_fails_on_overflow() {
+        assert_eq!(compound_ppm(u64::max_value(), 1_000_000, 1), Err("overflow in converting result to balance"));
+        assert_eq!(compound_ppm(1u64, 1_000_000, u64::max_value()), Err("overflow in compounding"));
+    }
+
+    #[test]
+    fn sqrt_product_rounds_down() {
+        assert_eq!(sqrt_product(0u64, 5), Ok(0));
+        assert_eq!(sqrt_product(1u64, 1), Ok(1));
+        assert_eq!(sqrt_product(4u64, 9), Ok(6));
+        assert_eq!(sqrt_product(2u64, 4), Ok(2));
+        assert_eq!(sqrt_product(u64::max_value(), u64::max_value()), Ok(u64::max_value()));
+    }
+
+    #[test]
+    fn convert_checks_the_target_width() {
+        assert_eq!(convert::<u64, u128>(u64::max_value()), Ok(u64::max_value() as u128));
+        assert_eq!(convert::<u128, u64>(u64::max_value() as u128), Ok(u64::max_value()));
+        assert_eq!(convert::<u128, u64>(u64::max_value() as u128 + 1), Err("overflow in converting result to balance"));
+    }
+
+    #[test]
+    fn draw_below_stays_below_the_bound() {
+        assert_eq!(draw_below(&[0xff; 32], 1u64), Ok(0));
+        assert!(draw_below(&[7; 32], 1_000u64).unwrap() < 1_000);
+        assert_eq!(draw_below(&[7; 32], 1_000u64), draw_below(&[7; 32], 1_000u64));
+        assert_eq!(draw_below(&[0xff; 32], 0u64), Err("division by zero"));
+    }
+
+    #[test]
+    fn scale_decimals_scales_both_ways() {
+        assert_eq!(scale_decimals(15u64, 6, 6), Ok(15));
+        assert_eq!(scale_decimals(15u64, 6, 8), Ok(1_500));
+        assert_eq!(scale_decimals(1_599u64, 8, 6), Ok(15));
+    }
+
+    #[test]
+    fn scale_decimals_fails_on_overflow_when_scaling_up() {
+        assert_eq!(scale_decimals(u64::max_value(), 0, 1), Err("overflow in scaling decimals"));
+        assert_eq!(scale_decimals(1u64, 0, 20), Err("overflow in calculating decimal scale"));
+    }
+
+    #[test]
+    fn scale_decimals_rounds_to_zero_when_the_divisor_overflows() {
+        assert_eq!(scale_decimals(1u64, 20, 0), Ok(0));
+        assert_eq!(scale_decimals(u64::max_value(), 20, 0), Ok(0));
+    }
+
+    #[test]
+    fn unit_is_a_power_of_ten() {
+        assert_eq!(unit::<u64>(0), Ok(1));
+        assert_eq!(unit::<u64>(6), Ok(1_000_000));
+        assert_eq!(unit::<u64>(18), Ok(1_000_000_000_000_000_000));
+    }
+}
@@ -0,0 +1,1195 @@
+// unit tests of the erc20 module against a mock runtime
+// the mock uses ed25519 accounts, so that off-chain signatures can be produced in the tests
+
+use super::*;
+use crate::erc20;
+use primitives::{ed25519, Blake2Hasher, H512};
+use runtime_io::{with_externalities, TestExternalities};
+use runtime_primitives::{BuildStorage, Ed25519Signature};
+use runtime_primitives::testing::{Digest, DigestItem, Header};
+use runtime_primitives::traits::{BlakeTwo256, IdentityLookup, OnFinalise};
+use support::{impl_outer_event, impl_outer_origin};
+
+impl_outer_origin! {
+    pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        balances<T>,
+        erc20<T>,
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type Digest = Digest;
+    type AccountId = H256;
+    type Lookup = IdentityLookup<H256>;
+    type Header = Header;
+    type Event = TestEvent;
+    type Log = DigestItem;
+}
+
+impl balances::Trait for Test {
+    type Balance = u128;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type EnsureAccountLiquid = ();
+    type Event = TestEvent;
+}
+
+impl Trait for Test {
+    type Event = TestEvent;
+    type TokenBalance = u128;
+    type TransferHandler = ();
+    type ForceOrigin = EnsureRoot<H256>;
+    type ModerationOrigin = EnsureRoot<H256>;
+    type ComplianceCheck = ();
+    type Signature = Ed25519Signature;
+    type LogicModules = ();
+    type FeeDestination = ();
+}
+
+type Erc20 = Module<Test>;
+type System = system::Module<Test>;
+type Balances = balances::Module<Test>;
+
+fn pair(seed: u8) -> ed25519::Pair {
+    ed25519::Pair::from_seed(&[seed; 32])
+}
+
+fn account(seed: u8) -> H256 {
+    H256::from(pair(seed).public().0)
+}
+
+fn alice() -> H256 {
+    account(1)
+}
+
+fn bob() -> H256 {
+    account(2)
+}
+
+fn charlie() -> H256 {
+    account(3)
+}
+
+// signs a message with the key of the account derived from the seed
+fn sign(seed: u8, message: &[u8]) -> Ed25519Signature {
+    Ed25519Signature(H512::from(pair(seed).sign(message).0))
+}
+
+fn erc20_config() -> GenesisConfig<Test> {
+    GenesisConfig {
+        max_batch_size: 16,
+        max_name_length: 64,
+        max_ticker_length: 32,
+        max_uri_length: 256,
+        max_memo_length: 128,
+        max_guardians: 16,
+        max_intents: 256,
+        max_sender_intents: 2,
+        max_external_id_length: 32,
+        max_page_size: 1000,
+        max_account_tokens: 4,
+        reserved_ticker_prefixes: vec![b"LP-".to_vec()],
+        logic_timelock: 10,
+        lockdown_delay: 10,
+        creation_base_fee: 0,
+        creation_target: 4,
+        issuer_deposit: 0,
+        moderation_bond: 0,
+        swap_fee: 3000,
+        buyout_execution_period: 10,
+        candle_period: 10,
+        max_candles: 4,
+        tokens: vec![(b"Genesis".to_vec(), b"GEN".to_vec(), 1000, alice(), vec![(bob(), 100)])],
+        synthetic_tokens: 0,
+        synthetic_balances: 0,
+        ethereum_snapshots: vec![],
+    }
+}
+
+// token 0 (GEN) has a supply of 1000 owned by alice, of which bob holds 100
+// alice and bob hold native balances, charlie holds none
+fn new_test_ext_with(erc20: GenesisConfig<Test>) -> TestExternalities<Blake2Hasher> {
+    let mut storage = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+    storage.extend(balances::GenesisConfig::<Test> {
+        transaction_base_fee: 0,
+        transaction_byte_fee: 0,
+        existential_deposit: 0,
+        transfer_fee: 0,
+        creation_fee: 0,
+        balances: vec![(alice(), 10_000), (bob(), 10_000)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+    storage.extend(erc20.build_storage().unwrap().0);
+    storage.into()
+}
+
+fn new_test_ext() -> TestExternalities<Blake2Hasher> {
+    new_test_ext_with(erc20_config())
+}
+
+fn events() -> Vec<Event<Test>> {
+    System::events().into_iter().filter_map(|record| match record.event {
+        TestEvent::erc20(event) => Some(event),
+        _ => None,
+    }).collect()
+}
+
+// genesis
+
+#[test]
+fn genesis_balances_are_indexed() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::balance_of(0, &alice()), 900);
+        assert_eq!(Erc20::balance_of(0, &bob()), 100);
+        assert_eq!(Erc20::holder_count(0), 2);
+        assert_eq!(Erc20::account_tokens(bob()), vec![0]);
+        assert_eq!(Erc20::owned_tokens(alice()), vec![0]);
+        assert!(events().is_empty());
+    });
+}
+
+// allowances
+
+#[test]
+fn transfer_from_spends_the_allowance_of_the_caller() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 50), Ok(()));
+
+        // the receiver is not the spender, so it cannot spend the allowance
+        assert_eq!(Erc20::transfer_from(Origin::signed(alice()), 0, bob(), alice(), 10), Err("Allowance does not exist."));
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 30), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 70);
+        assert_eq!(Erc20::balance_of(0, &alice()), 930);
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 20);
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 21), Err("Not enough allowance."));
+    });
+}
+
+#[test]
+fn failed_transfer_from_keeps_the_allowance() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 500), Ok(()));
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 200), Err("Not enough balance."));
+
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 500);
+        assert_eq!(Erc20::balance_of(0, &bob()), 100);
+    });
+}
+
+#[test]
+fn expired_allowance_cannot_be_spent() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve_until(Origin::signed(bob()), 0, charlie(), 50, 5), Ok(()));
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 10), Ok(()));
+
+        System::set_block_number(5);
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 10), Err("Allowance has expired."));
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 40);
+    });
+}
+
+// discarding balances
+
+#[test]
+fn discarded_balance_goes_to_the_owner() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::discard_balance(Origin::signed(bob()), 0), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 0);
+        assert_eq!(Erc20::balance_of(0, &alice()), 1000);
+        assert_eq!(Erc20::token_details(0).total_supply, 1000);
+        assert!(Erc20::account_tokens(bob()).is_empty());
+        assert!(events().contains(&RawEvent::BalanceDiscarded(0, bob(), alice(), 100)));
+
+        assert_eq!(Erc20::discard_balance(Origin::signed(bob()), 0), Err("Account does not own this token"));
+    });
+}
+
+#[test]
+fn discard_balance_respects_the_owner_and_locks() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::discard_balance(Origin::signed(alice()), 0), Err("Owner cannot discard its balance"));
+        <Erc20 as LockableToken<_, _>>::set_lock(0, *b"testlock", &bob(), 10);
+        assert_eq!(Erc20::discard_balance(Origin::signed(bob()), 0), Err("Balance is locked"));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 100);
+    });
+}
+
+// forced transfers
+
+#[test]
+fn force_transfer_cannot_move_a_locked_balance() {
+    with_externalities(&mut new_test_ext(), || {
+        <Erc20 as LockableToken<_, _>>::set_lock(0, *b"testlock", &bob(), 60);
+        assert!(Erc20::force_transfer(Origin::signed(alice()), 0, bob(), charlie(), 10).is_err());
+        assert_eq!(Erc20::force_transfer(Origin::ROOT, 0, bob(), charlie(), 50), Err("Balance is locked"));
+        assert_eq!(Erc20::force_transfer(Origin::ROOT, 0, bob(), charlie(), 40), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 60);
+        assert_eq!(Erc20::balance_of(0, &charlie()), 40);
+        assert!(events().contains(&RawEvent::ForcedTransfer(0, bob(), charlie(), 40)));
+    });
+}
+
+// reserved balances
+
+#[test]
+fn reserving_the_whole_balance_keeps_the_holder() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::reserve(0, &bob(), 100), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 0);
+        assert_eq!(Erc20::reserved_balance_of((0, bob())), 100);
+        assert!(Erc20::holds_token(0, &bob()));
+        assert_eq!(Erc20::holder_count(0), 2);
+        assert_eq!(Erc20::account_tokens(bob()), vec![0]);
+
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::unreserve(0, &bob(), 100), 0);
+        assert_eq!(Erc20::balance_of(0, &bob()), 100);
+        assert_eq!(Erc20::holder_count(0), 2);
+        assert_eq!(Erc20::account_tokens(bob()), vec![0]);
+    });
+}
+
+#[test]
+fn slashing_the_whole_reserve_removes_the_holder() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::reserve(0, &bob(), 100), Ok(()));
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::slash_reserved(0, &bob(), 100), 0);
+
+        assert!(!Erc20::holds_token(0, &bob()));
+        assert_eq!(Erc20::holder_count(0), 1);
+        assert!(Erc20::account_tokens(bob()).is_empty());
+        assert_eq!(Erc20::token_details(0).total_supply, 900);
+    });
+}
+
+#[test]
+fn reserved_tokens_count_towards_the_account_tokens() {
+    let mut config = erc20_config();
+    for ticker in [b"ONE", b"TWO", b"SIX", b"TEN"].iter() {
+        config.tokens.push((b"Token".to_vec(), ticker.to_vec(), 100, alice(), vec![]));
+    }
+    with_externalities(&mut new_test_ext_with(config), || {
+        // bob reserves all of token 0, then receives tokens until its slots are full
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::reserve(0, &bob(), 100), Ok(()));
+        for token_id in 1..4 {
+            assert_eq!(Erc20::transfer(Origin::signed(alice()), token_id, bob(), 10), Ok(()));
+        }
+        assert_eq!(Erc20::account_tokens(bob()).len(), 4);
+        assert_eq!(Erc20::transfer(Origin::signed(alice()), 4, bob(), 10), Err("Account holds too many tokens"));
+    });
+}
+
+// recovery
+
+fn dave() -> H256 {
+    account(4)
+}
+
+// charlie alone can recover bob to dave, without a delay
+fn start_recovery() {
+    assert_eq!(Erc20::set_guardians(Origin::signed(bob()), vec![charlie()], 1, 0), Ok(()));
+    assert_eq!(Erc20::approve_recovery(Origin::signed(charlie()), bob(), dave()), Ok(()));
+}
+
+#[test]
+fn recovery_moves_the_balances_and_revokes_the_allowances() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+        assert_eq!(Erc20::approve(Origin::signed(alice()), 0, bob(), 20), Ok(()));
+        start_recovery();
+        assert_eq!(Erc20::complete_recovery(Origin::signed(charlie()), bob()), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 0);
+        assert_eq!(Erc20::balance_of(0, &dave()), 100);
+        assert_eq!(Erc20::account_tokens(dave()), vec![0]);
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 0);
+        assert_eq!(Erc20::allowance(0, &alice(), &bob()), 0);
+        assert_eq!(Erc20::incoming_allowance_count(bob()), 0);
+        assert_eq!(Erc20::approval_count(0), 0);
+        assert!(Erc20::guardians(bob()).is_none());
+    });
+}
+
+#[test]
+fn recovery_moves_the_token_ownerships() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_guardians(Origin::signed(alice()), vec![charlie()], 1, 0), Ok(()));
+        assert_eq!(Erc20::approve_recovery(Origin::signed(charlie()), alice(), dave()), Ok(()));
+        assert_eq!(Erc20::complete_recovery(Origin::signed(charlie()), alice()), Ok(()));
+
+        assert_eq!(Erc20::owner_of(0), Some(dave()));
+        assert_eq!(Erc20::owned_tokens(dave()), vec![0]);
+        assert!(Erc20::owned_tokens(alice()).is_empty());
+        assert_eq!(Erc20::balance_of(0, &dave()), 900);
+    });
+}
+
+#[test]
+fn recovery_is_refused_while_balances_are_reserved_or_locked() {
+    with_externalities(&mut new_test_ext(), || {
+        start_recovery();
+
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::reserve(0, &bob(), 10), Ok(()));
+        assert_eq!(Erc20::complete_recovery(Origin::signed(charlie()), bob()), Err("Lost account has reserved balances"));
+        assert_eq!(<Erc20 as ReservableToken<_, _>>::unreserve(0, &bob(), 10), 0);
+
+        <Erc20 as LockableToken<_, _>>::set_lock(0, *b"testlock", &bob(), 10);
+        assert_eq!(Erc20::complete_recovery(Origin::signed(charlie()), bob()), Err("Lost account has locked balances"));
+        <Erc20 as LockableToken<_, _>>::remove_lock(0, *b"testlock", &bob());
+
+        assert_eq!(Erc20::complete_recovery(Origin::signed(charlie()), bob()), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &dave()), 100);
+    });
+}
+
+// destroying tokens
+
+#[test]
+fn destroy_refunds_escrows_and_hashed_timelocks() {
+    with_externalities(&mut new_test_ext(), || {
+        let hash = H256::from(runtime_io::keccak_256(&[7; 32]));
+        assert_eq!(Erc20::create_escrow(Origin::signed(bob()), 0, charlie(), 30, alice()), Ok(()));
+        assert_eq!(Erc20::lock_htlc(Origin::signed(bob()), 0, charlie(), 20, hash, 10), Ok(()));
+        assert_eq!(Erc20::open_holds(0), 2);
+
+        assert_eq!(Erc20::start_destroy(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::destroy_accounts(Origin::signed(alice()), 0, 10), Ok(()));
+        assert_eq!(Erc20::destroy_approvals(Origin::signed(alice()), 0, vec![]), Ok(()));
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 0), Err("Token still has escrows or hashed timelocks"));
+
+        // the payer cannot keep the token alive, the owner refunds its escrows and hashed timelocks
+        assert_eq!(Erc20::destroy_holds(Origin::signed(bob()), 0, vec![0], vec![hash]), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::destroy_holds(Origin::signed(alice()), 0, vec![0], vec![hash]), Ok(()));
+        assert_eq!(Erc20::open_holds(0), 0);
+        assert!(Erc20::escrow(0).is_none());
+        assert!(Erc20::htlc(hash).is_none());
+
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 0), Ok(()));
+        assert!(events().contains(&RawEvent::TokenDestroyed(0)));
+    });
+}
+
+#[test]
+fn destroy_waits_for_the_mint_offers() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Mintable".to_vec(), b"MNT".to_vec(), 10, None, true, false), Ok(()));
+        assert_eq!(Erc20::offer_mint(Origin::signed(alice()), 1, bob(), 5, 10), Ok(()));
+
+        assert_eq!(Erc20::start_destroy(Origin::signed(alice()), 1), Ok(()));
+        assert_eq!(Erc20::offer_mint(Origin::signed(alice()), 1, bob(), 5, 10), Err("Token is being destroyed"));
+        assert_eq!(Erc20::destroy_accounts(Origin::signed(alice()), 1, 10), Ok(()));
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 1), Err("Token still has mint offers"));
+
+        assert_eq!(Erc20::cancel_mint_offer(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 1), Ok(()));
+        assert_eq!(Erc20::mint_offer_count(1), 0);
+    });
+}
+
+#[test]
+fn revoking_an_allowance_removes_its_sub_allowances() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 50), Ok(()));
+        assert_eq!(Erc20::sub_approve(Origin::signed(charlie()), 0, bob(), dave(), 20), Ok(()));
+        assert_eq!(Erc20::sub_spenders((0, bob(), charlie())), vec![dave()]);
+
+        assert_eq!(Erc20::revoke_allowance(Origin::signed(bob()), 0, charlie()), Ok(()));
+        assert!(Erc20::sub_spenders((0, bob(), charlie())).is_empty());
+        assert_eq!(Erc20::sub_allowance((0, bob(), charlie(), dave())).value, 0);
+        assert_eq!(Erc20::transfer_from_sub(Origin::signed(dave()), 0, bob(), charlie(), dave(), 5), Err("Allowance does not exist."));
+    });
+}
+
+// liquidity pools
+
+#[test]
+fn first_deposit_locks_the_minimum_liquidity() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Other".to_vec(), b"OTH".to_vec(), 10_000, None, false, false), Ok(()));
+        assert_eq!(Erc20::create_pool(Origin::signed(alice()), 0, 1), Ok(()));
+        let pool = Erc20::pool(0).unwrap();
+
+        assert_eq!(Erc20::add_liquidity(Origin::signed(alice()), 0, 10, 10, 0), Err("Initial liquidity is too low"));
+        assert_eq!(Erc20::add_liquidity(Origin::signed(alice()), 0, 400, 10_000, 0), Ok(()));
+
+        // the square root of 400 * 10_000 is 2000, of which 1000 stay with the pool
+        assert_eq!(Erc20::balance_of(pool.lp_token, &alice()), 1000);
+        assert_eq!(Erc20::balance_of(pool.lp_token, &pool.account), 1000);
+        assert_eq!(Erc20::token_details(pool.lp_token).total_supply, 2000);
+
+        // withdrawing every redeemable lp token leaves the locked share of the reserves in the pool
+        assert_eq!(Erc20::remove_liquidity(Origin::signed(alice()), 0, 1000, 0, 0), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &pool.account), 200);
+        assert_eq!(Erc20::balance_of(1, &pool.account), 5_000);
+    });
+}
+
+// escrows
+
+#[test]
+fn escrowed_tokens_leave_the_payer_balance() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::create_escrow(Origin::signed(bob()), 0, charlie(), 30, alice()), Ok(()));
+        let escrow_account = Erc20::escrow_account(0).unwrap();
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 70);
+        assert_eq!(Erc20::reserved_balance_of((0, bob())), 0);
+        assert_eq!(Erc20::balance_of(0, &escrow_account), 30);
+
+        assert_eq!(Erc20::release_escrow(Origin::signed(charlie()), 0), Err("Only the payer or the arbiter can release an escrow"));
+        assert_eq!(Erc20::release_escrow(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &charlie()), 30);
+        assert_eq!(Erc20::balance_of(0, &escrow_account), 0);
+        assert!(Erc20::escrow(0).is_none());
+        assert_eq!(Erc20::open_holds(0), 0);
+    });
+}
+
+#[test]
+fn refunded_escrow_goes_back_to_the_payer() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::create_escrow(Origin::signed(bob()), 0, charlie(), 30, alice()), Ok(()));
+        assert_eq!(Erc20::refund_escrow(Origin::signed(bob()), 0), Err("Only the beneficiary or the arbiter can refund an escrow"));
+        assert_eq!(Erc20::refund_escrow(Origin::signed(charlie()), 0), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 100);
+        assert!(events().contains(&RawEvent::EscrowRefunded(0, bob(), 30)));
+    });
+}
+
+// intents
+
+#[test]
+fn intent_queue_is_bounded_per_sender() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::submit_intent(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+        assert_eq!(Erc20::submit_intent(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+        assert_eq!(Erc20::submit_intent(Origin::signed(bob()), 0, charlie(), 10), Err("Sender has too many queued intents"));
+        assert_eq!(Erc20::submit_intent(Origin::signed(alice()), 0, bob(), 5), Ok(()));
+        assert_eq!(Erc20::intents(0).len(), 3);
+    });
+}
+
+#[test]
+fn settled_intents_are_netted() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::submit_intent(Origin::signed(bob()), 0, alice(), 30), Ok(()));
+        assert_eq!(Erc20::submit_intent(Origin::signed(alice()), 0, bob(), 10), Ok(()));
+        assert_eq!(Erc20::settle_intents(Origin::signed(charlie()), 0, 10), Ok(()));
+
+        assert!(Erc20::intents(0).is_empty());
+        assert_eq!(Erc20::balance_of(0, &bob()), 80);
+        assert_eq!(Erc20::balance_of(0, &alice()), 920);
+        assert!(events().contains(&RawEvent::IntentsSettled(0, 2)));
+    });
+}
+
+// minting
+
+#[test]
+fn owner_mints_to_itself_and_offers_to_others() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Mintable".to_vec(), b"MNT".to_vec(), 10, Some(100), true, false), Ok(()));
+        assert_eq!(Erc20::mint(Origin::signed(bob()), 1, 10), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::mint(Origin::signed(alice()), 1, 10), Ok(()));
+        assert_eq!(Erc20::balance_of(1, &alice()), 20);
+
+        assert_eq!(Erc20::offer_mint(Origin::signed(alice()), 1, bob(), 5, 10), Ok(()));
+        assert_eq!(Erc20::accept_mint(Origin::signed(bob()), 0), Ok(()));
+        assert_eq!(Erc20::balance_of(1, &bob()), 5);
+        assert_eq!(Erc20::token_details(1).total_supply, 25);
+        assert_eq!(Erc20::mint(Origin::signed(alice()), 1, 80), Err("Minting would exceed the max supply"));
+    });
+}
+
+// decimals
+
+#[test]
+fn decimals_are_bounded_and_set_by_the_owner() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::metadata(0).decimals, 0);
+        assert_eq!(Erc20::set_decimals(Origin::signed(bob()), 0, 2), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 0, 19), Err("Token has too many decimals"));
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 0, 2), Ok(()));
+        assert_eq!(Erc20::metadata(0).decimals, 2);
+        assert!(events().contains(&RawEvent::DecimalsSet(0, 2)));
+    });
+}
+
+#[test]
+fn sale_price_is_quoted_per_whole_token() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 0, 2), Ok(()));
+        assert_eq!(Erc20::start_sale(Origin::signed(alice()), 0, 150, 500, 0, 0, 10), Ok(()));
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 0, 3), Err("Token has a sale"));
+
+        // two whole tokens cost twice the price, a hundredth of a token rounds up
+        assert_eq!(Erc20::purchase(Origin::signed(bob()), 0, 200), Ok(()));
+        assert_eq!(Erc20::purchase(Origin::signed(bob()), 0, 1), Ok(()));
+        assert!(events().contains(&RawEvent::Purchased(0, bob(), 200, 300)));
+        assert!(events().contains(&RawEvent::Purchased(0, bob(), 1, 2)));
+        assert_eq!(Balances::free_balance(&bob()), 10_000 - 302);
+    });
+}
+
+#[test]
+fn first_deposit_scales_the_amounts_to_the_lp_decimals() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Other".to_vec(), b"OTH".to_vec(), 10_000, None, false, false), Ok(()));
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 1, 2), Ok(()));
+        assert_eq!(Erc20::create_pool(Origin::signed(alice()), 0, 1), Ok(()));
+        let pool = Erc20::pool(0).unwrap();
+        assert_eq!(Erc20::metadata(pool.lp_token).decimals, 2);
+
+        // 40 whole GEN are 4000 units at 2 decimals, the square root of 4000 * 1000 is 2000
+        assert_eq!(Erc20::add_liquidity(Origin::signed(alice()), 0, 40, 1000, 0), Ok(()));
+        assert_eq!(Erc20::balance_of(pool.lp_token, &alice()), 1000);
+        assert_eq!(Erc20::balance_of(0, &pool.account), 40);
+    });
+}
+
+// pausing
+
+#[test]
+fn paused_token_cannot_be_transferred() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::pause(Origin::signed(bob()), 0), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::pause(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::pause(Origin::signed(alice()), 0), Err("Token is already paused"));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Token transfers are paused"));
+
+        assert_eq!(Erc20::unpause(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+        assert!(events().contains(&RawEvent::Paused(0)));
+        assert!(events().contains(&RawEvent::Unpaused(0)));
+    });
+}
+
+// denylist
+
+#[test]
+fn denied_token_cannot_be_transferred_or_approved() {
+    with_externalities(&mut new_test_ext(), || {
+        assert!(Erc20::deny_token(Origin::signed(alice()), 0).is_err());
+        assert_eq!(Erc20::deny_token(Origin::ROOT, 1), Err("Token does not exist"));
+        assert_eq!(Erc20::deny_token(Origin::ROOT, 0), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Token is denied"));
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 10), Err("Token is denied"));
+
+        assert_eq!(Erc20::allow_token(Origin::ROOT, 0), Ok(()));
+        assert_eq!(Erc20::allow_token(Origin::ROOT, 0), Err("Token is not denied"));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+    });
+}
+
+// freezing accounts
+
+#[test]
+fn frozen_account_cannot_send_or_receive() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::freeze_account(Origin::signed(bob()), 0, charlie()), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::freeze_account(Origin::signed(alice()), 0, bob()), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Sender account is frozen"));
+        assert_eq!(Erc20::transfer(Origin::signed(alice()), 0, bob(), 10), Err("Receiver account is frozen"));
+
+        assert_eq!(Erc20::thaw_account(Origin::signed(alice()), 0, bob()), Ok(()));
+        assert_eq!(Erc20::thaw_account(Origin::signed(alice()), 0, bob()), Err("Account is not frozen"));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+    });
+}
+
+// blacklist
+
+#[test]
+fn blacklisted_account_cannot_send_receive_or_approve() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::add_to_blacklist(Origin::signed(alice()), 0, bob()), Ok(()));
+        assert_eq!(Erc20::add_to_blacklist(Origin::signed(alice()), 0, bob()), Err("Account is already blacklisted"));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Sender account is blacklisted"));
+        assert_eq!(Erc20::transfer(Origin::signed(alice()), 0, bob(), 10), Err("Receiver account is blacklisted"));
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 10), Err("Owner account is blacklisted"));
+        assert_eq!(Erc20::approve(Origin::signed(alice()), 0, bob(), 10), Err("Spender account is blacklisted"));
+
+        assert_eq!(Erc20::remove_from_blacklist(Origin::signed(alice()), 0, bob()), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+    });
+}
+
+// whitelist
+
+#[test]
+fn whitelisted_token_only_reaches_whitelisted_accounts() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_transfer_mode(Origin::signed(bob()), 0, TransferMode::Whitelisted), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_transfer_mode(Origin::signed(alice()), 0, TransferMode::Whitelisted), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Receiver account is not whitelisted"));
+
+        assert_eq!(Erc20::add_to_whitelist(Origin::signed(alice()), 0, charlie()), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+
+        assert_eq!(Erc20::remove_from_whitelist(Origin::signed(alice()), 0, charlie()), Ok(()));
+        assert_eq!(Erc20::remove_from_whitelist(Origin::signed(alice()), 0, charlie()), Err("Account is not whitelisted"));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Receiver account is not whitelisted"));
+
+        assert_eq!(Erc20::set_transfer_mode(Origin::signed(alice()), 0, TransferMode::Open), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+    });
+}
+
+// admin sunset
+
+#[test]
+fn sunset_disables_the_admin_calls() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::schedule_admin_sunset(Origin::signed(alice()), 0, 10), Ok(()));
+        assert_eq!(Erc20::schedule_admin_sunset(Origin::signed(alice()), 0, 11), Err("Admin sunset cannot be postponed"));
+        assert_eq!(Erc20::schedule_admin_sunset(Origin::signed(alice()), 0, 5), Ok(()));
+        assert_eq!(Erc20::admin_sunset(0), Some(5));
+
+        System::set_block_number(5);
+        assert_eq!(Erc20::pause(Origin::signed(alice()), 0), Err("Admin rights of this token have been sunset"));
+        assert_eq!(Erc20::force_transfer(Origin::ROOT, 0, bob(), charlie(), 10), Err("Admin rights of this token have been sunset"));
+        // holders are not affected
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+    });
+}
+
+// signed approvals and transfers
+
+#[test]
+fn permit_sets_an_allowance_once() {
+    with_externalities(&mut new_test_ext(), || {
+        let message = (&b"erc20:permit"[..], Erc20::signing_domain(), 0u32, bob(), charlie(), 50u128, 0u64, 10u64).encode();
+        let signature = sign(2, &message);
+
+        assert_eq!(Erc20::permit(Origin::signed(alice()), 0, bob(), charlie(), 60, 10, signature.clone()), Err("Invalid signature"));
+        assert_eq!(Erc20::permit(Origin::signed(alice()), 0, bob(), charlie(), 50, 10, signature.clone()), Ok(()));
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 50);
+        assert_eq!(Erc20::nonce_of(bob()), 1);
+
+        // the nonce has moved on, so the signature cannot be replayed
+        assert_eq!(Erc20::permit(Origin::signed(alice()), 0, bob(), charlie(), 50, 10, signature), Err("Invalid signature"));
+    });
+}
+
+#[test]
+fn expired_permit_is_rejected() {
+    with_externalities(&mut new_test_ext(), || {
+        let message = (&b"erc20:permit"[..], Erc20::signing_domain(), 0u32, bob(), charlie(), 50u128, 0u64, 10u64).encode();
+        System::set_block_number(11);
+        assert_eq!(Erc20::permit(Origin::signed(alice()), 0, bob(), charlie(), 50, 10, sign(2, &message)), Err("Permit has expired"));
+    });
+}
+
+#[test]
+fn relayed_transfer_uses_the_nonce_of_the_signer() {
+    with_externalities(&mut new_test_ext(), || {
+        let message = (&b"erc20:transfer"[..], Erc20::signing_domain(), 0u32, bob(), charlie(), 30u128, 0u64).encode();
+        let signature = sign(2, &message);
+
+        assert_eq!(Erc20::transfer_by_signature(Origin::signed(alice()), 0, bob(), charlie(), 30, 1, signature.clone()), Err("Invalid nonce"));
+        assert_eq!(Erc20::transfer_by_signature(Origin::signed(alice()), 0, bob(), alice(), 30, 0, signature.clone()), Err("Invalid signature"));
+        assert_eq!(Erc20::transfer_by_signature(Origin::signed(alice()), 0, bob(), charlie(), 30, 0, signature.clone()), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &charlie()), 30);
+        assert_eq!(Erc20::nonce_of(bob()), 1);
+        assert_eq!(Erc20::transfer_by_signature(Origin::signed(alice()), 0, bob(), charlie(), 30, 0, signature), Err("Invalid nonce"));
+    });
+}
+
+#[test]
+fn signed_batch_applies_every_transfer_or_none() {
+    with_externalities(&mut new_test_ext(), || {
+        let transfers = vec![(charlie(), 30u128), (dave(), 20u128)];
+        let batch_hash = BlakeTwo256::hash_of(&transfers);
+        let message = (&b"erc20:batch"[..], Erc20::signing_domain(), 0u32, bob(), batch_hash, 0u64).encode();
+        assert_eq!(Erc20::batch_transfer_by_signature(Origin::signed(alice()), 0, bob(), transfers, 0, sign(2, &message)), Ok(()));
+
+        assert_eq!(Erc20::balance_of(0, &bob()), 50);
+        assert_eq!(Erc20::balance_of(0, &charlie()), 30);
+        assert_eq!(Erc20::balance_of(0, &dave()), 20);
+        assert_eq!(Erc20::nonce_of(bob()), 1);
+
+        // the second leg overdraws bob, so the first is not applied either and the nonce is kept
+        let transfers = vec![(charlie(), 30u128), (dave(), 30u128)];
+        let batch_hash = BlakeTwo256::hash_of(&transfers);
+        let message = (&b"erc20:batch"[..], Erc20::signing_domain(), 0u32, bob(), batch_hash, 1u64).encode();
+        assert_eq!(Erc20::batch_transfer_by_signature(Origin::signed(alice()), 0, bob(), transfers, 1, sign(2, &message)), Err("Not enough balance."));
+        assert_eq!(Erc20::balance_of(0, &bob()), 50);
+        assert_eq!(Erc20::nonce_of(bob()), 1);
+    });
+}
+
+// minimum balance
+
+#[test]
+fn balance_left_below_the_minimum_is_removed_as_dust() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_min_balance(Origin::signed(bob()), 0, 10), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_min_balance(Origin::signed(alice()), 0, 10), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 5), Err("Receiver balance would be below the minimum balance"));
+
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 95), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &bob()), 0);
+        assert_eq!(Erc20::balance_of(0, &charlie()), 95);
+        assert_eq!(Erc20::token_details(0).total_supply, 995);
+        assert!(Erc20::account_tokens(bob()).is_empty());
+        assert!(events().contains(&RawEvent::DustLost(0, bob(), 5)));
+    });
+}
+
+// sub-allowances
+
+#[test]
+fn sub_allowance_is_carved_out_of_the_allowance() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 50), Ok(()));
+        assert_eq!(Erc20::sub_approve(Origin::signed(charlie()), 0, bob(), dave(), 60), Err("Not enough allowance."));
+        assert_eq!(Erc20::sub_approve(Origin::signed(charlie()), 0, bob(), dave(), 20), Ok(()));
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 30);
+
+        assert_eq!(Erc20::transfer_from_sub(Origin::signed(dave()), 0, bob(), charlie(), dave(), 15), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &dave()), 15);
+        assert_eq!(Erc20::sub_allowance((0, bob(), charlie(), dave())).value, 5);
+        assert_eq!(Erc20::transfer_from_sub(Origin::signed(dave()), 0, bob(), charlie(), dave(), 10), Err("Not enough allowance."));
+    });
+}
+
+// legacy balances
+
+#[test]
+fn legacy_balance_is_migrated_and_indexed_when_read() {
+    with_externalities(&mut new_test_ext(), || {
+        <BalanceOf<Test>>::insert((0, charlie()), 50);
+        assert_eq!(Erc20::holder_count(0), 2);
+
+        assert_eq!(Erc20::balance_of(0, &charlie()), 50);
+        assert!(!<BalanceOf<Test>>::exists((0, charlie())));
+        assert!(Erc20::has_balance(0, &charlie()));
+        assert_eq!(Erc20::holder_count(0), 3);
+        assert_eq!(Erc20::holders(0, 2, 10), vec![(charlie(), 50)]);
+    });
+}
+
+// legacy allowances
+
+#[test]
+fn legacy_allowance_is_migrated_and_spendable() {
+    with_externalities(&mut new_test_ext(), || {
+        <Allowance<Test>>::insert((0, bob(), charlie()), 40);
+
+        assert_eq!(Erc20::transfer_from(Origin::signed(charlie()), 0, bob(), alice(), 30), Ok(()));
+        assert!(!<Allowance<Test>>::exists((0, bob(), charlie())));
+        assert_eq!(Erc20::allowance(0, &bob(), &charlie()), 10);
+        assert_eq!(Erc20::approved_spenders((0, bob())), vec![charlie()]);
+        assert_eq!(Erc20::incoming_allowance_count(charlie()), 1);
+    });
+}
+
+// snapshots
+
+#[test]
+fn snapshot_of_several_pages_needs_a_paused_token() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::snapshot_balances(Origin::signed(alice()), 0, 1), Err("Token must be paused for a snapshot of more than one page"));
+        assert_eq!(Erc20::snapshot_count(0), 0);
+
+        assert_eq!(Erc20::pause(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::snapshot_balances(Origin::signed(alice()), 0, 1), Ok(()));
+        assert_eq!(Erc20::snapshot_cursor(0), Some((0, 1)));
+        assert!(Erc20::snapshot((0, 0)).is_none());
+
+        assert_eq!(Erc20::snapshot_balances(Origin::signed(alice()), 0, 1), Ok(()));
+        assert_eq!(Erc20::snapshot_cursor(0), None);
+        assert!(Erc20::snapshot((0, 0)).is_some());
+        assert_eq!(Erc20::snapshot_count(0), 1);
+
+        let storage_key = Erc20::snapshot_storage_key(0, 0);
+        assert_eq!(runtime_io::child_storage(&storage_key, &alice().encode()), Some(900u128.encode()));
+        assert_eq!(runtime_io::child_storage(&storage_key, &bob().encode()), Some(100u128.encode()));
+    });
+}
+
+// incoming allowances
+
+#[test]
+fn incoming_allowances_are_paged_and_stay_contiguous() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::approve(Origin::signed(bob()), 0, charlie(), 50), Ok(()));
+        assert_eq!(Erc20::approve(Origin::signed(alice()), 0, charlie(), 70), Ok(()));
+        assert_eq!(Erc20::incoming_allowances_of(&charlie(), 0, 10), vec![(0, bob(), 50), (0, alice(), 70)]);
+        assert_eq!(Erc20::incoming_allowances_of(&charlie(), 1, 1), vec![(0, alice(), 70)]);
+
+        // the last allowance moves into the slot of the revoked one
+        assert_eq!(Erc20::revoke_allowance(Origin::signed(bob()), 0, charlie()), Ok(()));
+        assert_eq!(Erc20::incoming_allowance_count(charlie()), 1);
+        assert_eq!(Erc20::incoming_allowances_of(&charlie(), 0, 10), vec![(0, alice(), 70)]);
+    });
+}
+
+// lockdown
+
+#[test]
+fn guardian_locks_down_an_account_until_the_delay_passes() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_lockdown_guardian(Origin::signed(bob()), Some(charlie())), Ok(()));
+        assert_eq!(Erc20::panic_freeze(Origin::signed(alice()), bob()), Err("Sender is not the lockdown guardian of the account"));
+        assert_eq!(Erc20::panic_freeze(Origin::signed(charlie()), bob()), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Err("Sender account is locked down"));
+        assert_eq!(Erc20::set_lockdown_guardian(Origin::signed(bob()), None), Err("Account is locked down"));
+
+        assert_eq!(Erc20::unlock(Origin::signed(bob())), Err("Unlock has not been requested"));
+        assert_eq!(Erc20::request_unlock(Origin::signed(bob())), Ok(()));
+        assert_eq!(Erc20::unlock(Origin::signed(bob())), Err("Unlock delay has not passed"));
+
+        System::set_block_number(10);
+        assert_eq!(Erc20::unlock(Origin::signed(bob())), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 10), Ok(()));
+        assert!(events().contains(&RawEvent::LockedDown(bob())));
+        assert!(events().contains(&RawEvent::Unlocked(bob())));
+    });
+}
+
+#[test]
+fn guardian_freeze_cancels_a_pending_unlock() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_lockdown_guardian(Origin::signed(bob()), Some(charlie())), Ok(()));
+        assert_eq!(Erc20::panic_freeze(Origin::signed(charlie()), bob()), Ok(()));
+        assert_eq!(Erc20::request_unlock(Origin::signed(bob())), Ok(()));
+        assert_eq!(Erc20::panic_freeze(Origin::signed(charlie()), bob()), Ok(()));
+
+        System::set_block_number(10);
+        assert_eq!(Erc20::unlock(Origin::signed(bob())), Err("Unlock has not been requested"));
+        assert!(Erc20::is_locked_down(bob()));
+    });
+}
+
+// metadata
+
+#[test]
+fn owner_renames_a_token_and_moves_its_ticker() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_metadata(Origin::signed(bob()), 0, b"Gold".to_vec(), b"GLD".to_vec()), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_metadata(Origin::signed(alice()), 0, b"Gold".to_vec(), b"LP-GLD".to_vec()), Err("Ticker prefix is reserved"));
+        assert_eq!(Erc20::set_metadata(Origin::signed(alice()), 0, b"Gold".to_vec(), b"GLD".to_vec()), Ok(()));
+
+        assert_eq!(Erc20::metadata(0).name, b"Gold".to_vec());
+        assert_eq!(Erc20::token_id_of(b"gld"), Some(0));
+        assert_eq!(Erc20::token_id_of(b"GEN"), None);
+
+        // changing only the case keeps the registered ticker
+        assert_eq!(Erc20::set_metadata(Origin::signed(alice()), 0, b"Gold".to_vec(), b"Gld".to_vec()), Ok(()));
+        assert_eq!(Erc20::token_id_of(b"GLD"), Some(0));
+        assert!(events().contains(&RawEvent::MetadataUpdated(0, b"Gold".to_vec(), b"Gld".to_vec(), None)));
+    });
+}
+
+// token uri
+
+#[test]
+fn owner_sets_and_removes_the_token_uri() {
+    with_externalities(&mut new_test_ext(), || {
+        let uri = b"https://example.com/gen.json".to_vec();
+        assert_eq!(Erc20::set_uri(Origin::signed(bob()), 0, Some(uri.clone())), Err("Only the token owner or metadata manager can do this"));
+        assert_eq!(Erc20::set_uri(Origin::signed(alice()), 0, Some(vec![b'a'; 257])), Err("token uri is too long"));
+        assert_eq!(Erc20::set_uri(Origin::signed(alice()), 0, Some(uri.clone())), Ok(()));
+        assert_eq!(Erc20::metadata(0).uri, Some(uri.clone()));
+
+        assert_eq!(Erc20::set_uri(Origin::signed(alice()), 0, None), Ok(()));
+        assert_eq!(Erc20::metadata(0).uri, None);
+        assert!(events().contains(&RawEvent::UriUpdated(0, Some(uri))));
+        assert!(events().contains(&RawEvent::UriUpdated(0, None)));
+    });
+}
+
+// metadata hash and freezing
+
+#[test]
+fn frozen_metadata_cannot_be_changed() {
+    with_externalities(&mut new_test_ext(), || {
+        let metadata_hash = H256::repeat_byte(7);
+        assert_eq!(Erc20::set_metadata_hash(Origin::signed(bob()), 0, Some(metadata_hash)), Err("Only the token owner or metadata manager can do this"));
+        assert_eq!(Erc20::set_metadata_hash(Origin::signed(alice()), 0, Some(metadata_hash)), Ok(()));
+        assert_eq!(Erc20::metadata(0).metadata_hash, Some(metadata_hash));
+
+        assert_eq!(Erc20::freeze_metadata(Origin::signed(bob()), 0), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::freeze_metadata(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::set_metadata_hash(Origin::signed(alice()), 0, None), Err("Token metadata is frozen"));
+        assert_eq!(Erc20::set_metadata(Origin::signed(alice()), 0, b"Gold".to_vec(), b"GLD".to_vec()), Err("Token metadata is frozen"));
+        assert_eq!(Erc20::set_uri(Origin::signed(alice()), 0, None), Err("Token metadata is frozen"));
+        assert_eq!(Erc20::set_decimals(Origin::signed(alice()), 0, 6), Err("Token metadata is frozen"));
+
+        assert_eq!(Erc20::metadata(0).metadata_hash, Some(metadata_hash));
+        assert!(events().contains(&RawEvent::MetadataFrozen(0)));
+    });
+}
+
+// ethereum claims
+
+#[cfg(feature = "ethereum-migration")]
+fn ethereum_test_ext() -> TestExternalities<Blake2Hasher> {
+    let mut config = erc20_config();
+    config.ethereum_snapshots = vec![(b"Migrated".to_vec(), b"MIG".to_vec(), alice(), vec![([1; 20], 40), ([2; 20], 60), ([1; 20], 10)])];
+    new_test_ext_with(config)
+}
+
+#[cfg(feature = "ethereum-migration")]
+#[test]
+fn snapshot_supply_starts_out_unclaimed() {
+    with_externalities(&mut ethereum_test_ext(), || {
+        assert_eq!(Erc20::token_details(1).total_supply, 110);
+        assert_eq!(Erc20::balance_of(1, &alice()), 0);
+        assert_eq!(Erc20::ethereum_claim((1, [1; 20])), Some(50));
+        assert_eq!(Erc20::unclaimed_count(1), 2);
+
+        assert_eq!(Erc20::claim(Origin::signed(bob()), 1, EcdsaSignature([0; 32], [0; 32], 27)), Err("Invalid ethereum signature"));
+    });
+}
+
+#[cfg(feature = "ethereum-migration")]
+#[test]
+fn destroy_removes_the_unclaimed_balances() {
+    with_externalities(&mut ethereum_test_ext(), || {
+        assert_eq!(Erc20::destroy_claims(Origin::signed(alice()), 1, vec![[1; 20]]), Err("Token is not being destroyed"));
+        assert_eq!(Erc20::start_destroy(Origin::signed(alice()), 1), Ok(()));
+        assert_eq!(Erc20::destroy_accounts(Origin::signed(alice()), 1, 10), Ok(()));
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 1), Err("Token still has unclaimed balances"));
+
+        assert_eq!(Erc20::destroy_claims(Origin::signed(alice()), 1, vec![[1; 20], [2; 20]]), Ok(()));
+        assert_eq!(Erc20::unclaimed_count(1), 0);
+        assert!(events().contains(&RawEvent::ClaimsDestroyed(1, 0)));
+        assert_eq!(Erc20::finish_destroy(Origin::signed(alice()), 1), Ok(()));
+    });
+}
+
+// quiet accounts
+
+#[test]
+fn transfers_between_quiet_accounts_are_evented_per_block() {
+    with_externalities(&mut new_test_ext(), || {
+        assert!(Erc20::set_quiet_account(Origin::signed(alice()), alice(), true).is_err());
+        assert_eq!(Erc20::set_quiet_account(Origin::ROOT, alice(), true), Ok(()));
+        assert_eq!(Erc20::set_quiet_account(Origin::ROOT, bob(), true), Ok(()));
+
+        assert_eq!(Erc20::transfer(Origin::signed(alice()), 0, bob(), 10), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, alice(), 5), Ok(()));
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 1), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &bob()), 104);
+
+        <Erc20 as OnFinalise<u64>>::on_finalise(1);
+        let transfers: Vec<_> = events().into_iter().filter(|event| match event {
+            RawEvent::Transfer(..) => true,
+            _ => false,
+        }).collect();
+        assert_eq!(transfers, vec![RawEvent::Transfer(0, bob(), charlie(), 1, None)]);
+        assert!(events().contains(&RawEvent::QuietTransfers(vec![(0, 2, 15)])));
+        assert!(Erc20::quiet_transfer_totals().is_empty());
+    });
+}
+
+// moderation
+
+fn moderation_test_ext() -> TestExternalities<Blake2Hasher> {
+    let mut config = erc20_config();
+    config.moderation_bond = 100;
+    new_test_ext_with(config)
+}
+
+#[test]
+fn accepted_moderation_corrects_frozen_metadata() {
+    with_externalities(&mut moderation_test_ext(), || {
+        assert_eq!(Erc20::freeze_metadata(Origin::signed(alice()), 0), Ok(()));
+        assert_eq!(Erc20::propose_moderation(Origin::signed(charlie()), 0, b"Fixed".to_vec(), b"FIX".to_vec()), Err("Not enough balance to pay the moderation bond"));
+        assert_eq!(Erc20::propose_moderation(Origin::signed(bob()), 0, b"Fixed".to_vec(), b"FIX".to_vec()), Ok(()));
+        assert_eq!(Erc20::propose_moderation(Origin::signed(alice()), 0, b"Other".to_vec(), b"OTH".to_vec()), Err("Token already has a moderation proposal"));
+        assert_eq!(Balances::reserved_balance(&bob()), 100);
+
+        assert!(Erc20::accept_moderation(Origin::signed(alice()), 0).is_err());
+        assert_eq!(Erc20::accept_moderation(Origin::ROOT, 0), Ok(()));
+        assert_eq!(Erc20::metadata(0).name, b"Fixed".to_vec());
+        assert_eq!(Erc20::token_id_of(b"FIX"), Some(0));
+        assert_eq!(Balances::reserved_balance(&bob()), 0);
+        assert_eq!(Balances::free_balance(&bob()), 10_000);
+        assert!(Erc20::moderation_proposal(0).is_none());
+    });
+}
+
+#[test]
+fn rejected_moderation_slashes_the_bond() {
+    with_externalities(&mut moderation_test_ext(), || {
+        assert_eq!(Erc20::propose_moderation(Origin::signed(bob()), 0, b"Fixed".to_vec(), b"FIX".to_vec()), Ok(()));
+        assert_eq!(Erc20::reject_moderation(Origin::ROOT, 0), Ok(()));
+
+        assert_eq!(Erc20::metadata(0).name, b"Genesis".to_vec());
+        assert_eq!(Balances::reserved_balance(&bob()), 0);
+        assert_eq!(Balances::free_balance(&bob()), 9_900);
+        assert_eq!(Erc20::reject_moderation(Origin::ROOT, 0), Err("Token has no moderation proposal"));
+        assert!(events().contains(&RawEvent::ModerationRejected(0, bob(), 100)));
+    });
+}
+
+// dust sweeping
+
+#[test]
+fn dust_is_swept_through_the_pool_into_the_target_token() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Other".to_vec(), b"OTH".to_vec(), 10_000, None, false, false), Ok(()));
+        assert_eq!(Erc20::create_pool(Origin::signed(alice()), 0, 1), Ok(()));
+        assert_eq!(Erc20::add_liquidity(Origin::signed(alice()), 0, 800, 8000, 0), Ok(()));
+        let pool = Erc20::pool(0).unwrap();
+
+        assert_eq!(Erc20::sweep_dust(Origin::signed(bob()), vec![0], 1), Err("Balance is above the dust threshold"));
+        assert_eq!(Erc20::set_dust_threshold(Origin::signed(bob()), 0, 100), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_dust_threshold(Origin::signed(alice()), 0, 100), Ok(()));
+        assert_eq!(Erc20::sweep_dust(Origin::signed(bob()), vec![0, 0], 1), Err("Token is swept twice"));
+        assert_eq!(Erc20::sweep_dust(Origin::signed(bob()), vec![1], 1), Err("Cannot sweep the target token"));
+
+        // 99 GEN are left after the swap fee, for 8000 * 99 / 899 OTH
+        assert_eq!(Erc20::sweep_dust(Origin::signed(bob()), vec![0], 1), Ok(()));
+        assert!(!Erc20::has_balance(0, &bob()));
+        assert_eq!(Erc20::balance_of(1, &bob()), 880);
+        assert_eq!(Erc20::balance_of(0, &pool.account), 900);
+        assert_eq!(Erc20::balance_of(1, &pool.account), 7120);
+        assert!(events().contains(&RawEvent::DustSwept(bob(), 1, 1, 880)));
+    });
+}
+
+// rounding policy
+
+#[test]
+fn rounding_policy_rounds_the_transfer_burn() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::set_transfer_burn_rate(Origin::signed(alice()), 0, 250_000), Ok(()));
+        assert_eq!(Erc20::transfer_burn(0, 6), Ok(1));
+
+        assert_eq!(Erc20::set_rounding_policy(Origin::signed(bob()), 0, Some(Rounding::Up)), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_rounding_policy(Origin::signed(alice()), 0, Some(Rounding::Up)), Ok(()));
+        assert_eq!(Erc20::transfer_burn(0, 5), Ok(2));
+
+        // a quarter of 6 is 1.5 and of 2 is 0.5, halves go to the even unit
+        assert_eq!(Erc20::set_rounding_policy(Origin::signed(alice()), 0, Some(Rounding::HalfEven)), Ok(()));
+        assert_eq!(Erc20::transfer_burn(0, 6), Ok(2));
+        assert_eq!(Erc20::transfer_burn(0, 2), Ok(0));
+
+        assert_eq!(Erc20::transfer(Origin::signed(bob()), 0, charlie(), 6), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &charlie()), 4);
+        assert_eq!(Erc20::token_details(0).total_supply, 998);
+
+        assert_eq!(Erc20::set_rounding_policy(Origin::signed(alice()), 0, None), Ok(()));
+        assert_eq!(Erc20::transfer_burn(0, 6), Ok(1));
+        assert!(events().contains(&RawEvent::RoundingPolicyChanged(0, Some(Rounding::HalfEven))));
+    });
+}
+
+// price candles
+
+#[test]
+fn swaps_are_recorded_in_a_ring_of_candles() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::init(Origin::signed(alice()), b"Other".to_vec(), b"OTH".to_vec(), 10_000, None, false, false), Ok(()));
+        assert_eq!(Erc20::create_pool(Origin::signed(alice()), 0, 1), Ok(()));
+        assert_eq!(Erc20::add_liquidity(Origin::signed(alice()), 0, 800, 8000, 0), Ok(()));
+        assert!(Erc20::candles(0).is_empty());
+
+        // the price starts at 10 OTH per GEN, drops to 7 and is brought back to 10
+        assert_eq!(Erc20::swap_exact_in(Origin::signed(bob()), 0, 0, 100, 0), Ok(()));
+        assert_eq!(Erc20::swap_exact_in(Origin::signed(alice()), 0, 1, 1000, 0), Ok(()));
+        assert_eq!(Erc20::candles(0), vec![Candle { period: 0, open: 10, high: 10, low: 7, close: 10, volume: 210 }]);
+
+        // with four slots, period 4 replaces period 0
+        System::set_block_number(40);
+        assert_eq!(Erc20::swap_exact_in(Origin::signed(bob()), 0, 1, 100, 0), Ok(()));
+        assert_eq!(Erc20::candles(0), vec![Candle { period: 4, open: 10, high: 10, low: 10, close: 10, volume: 9 }]);
+    });
+}
+
+// metadata manager
+
+#[test]
+fn metadata_manager_only_edits_the_uri_and_hash() {
+    with_externalities(&mut new_test_ext(), || {
+        let uri = b"https://example.com/gen.json".to_vec();
+        assert_eq!(Erc20::set_metadata_manager(Origin::signed(bob()), 0, Some(bob())), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_metadata_manager(Origin::signed(alice()), 0, Some(bob())), Ok(()));
+
+        assert_eq!(Erc20::set_uri(Origin::signed(bob()), 0, Some(uri.clone())), Ok(()));
+        assert_eq!(Erc20::set_metadata_hash(Origin::signed(bob()), 0, Some(H256::repeat_byte(7))), Ok(()));
+        assert_eq!(Erc20::metadata(0).uri, Some(uri));
+        assert_eq!(Erc20::set_metadata(Origin::signed(bob()), 0, b"Gold".to_vec(), b"GLD".to_vec()), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::pause(Origin::signed(bob()), 0), Err("Only the token owner can do this"));
+        assert_eq!(Erc20::set_metadata_manager(Origin::signed(bob()), 0, None), Err("Only the token owner can do this"));
+
+        assert_eq!(Erc20::set_metadata_manager(Origin::signed(alice()), 0, None), Ok(()));
+        assert_eq!(Erc20::set_uri(Origin::signed(bob()), 0, None), Err("Only the token owner or metadata manager can do this"));
+        assert!(events().contains(&RawEvent::MetadataManagerSet(0, Some(bob()))));
+    });
+}
+
+// fair launches
+
+#[test]
+fn oversubscribed_fair_launch_fills_the_cap_and_refunds_the_rest() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Balances::transfer(Origin::signed(alice()), charlie(), 1000), Ok(()));
+        assert_eq!(Erc20::start_fair_launch(Origin::signed(alice()), 0, 2, 100, 0, 0, 10), Ok(()));
+
+        // orders may exceed the cap, one per buyer
+        assert_eq!(Erc20::purchase(Origin::signed(bob()), 0, 80), Ok(()));
+        assert_eq!(Erc20::purchase(Origin::signed(bob()), 0, 10), Err("Account already has an order in this fair launch"));
+        assert_eq!(Erc20::purchase(Origin::signed(charlie()), 0, 60), Ok(()));
+        assert_eq!(Erc20::fair_launch(0).unwrap().ordered, 140);
+        assert_eq!(Erc20::finalise_sale(Origin::signed(bob()), 0), Err("Sale has not ended"));
+
+        System::set_block_number(11);
+        assert_eq!(Erc20::finalise_sale(Origin::signed(bob()), 0), Ok(()));
+        let draw = Erc20::fair_launch(0).unwrap().draw.unwrap();
+        assert!(draw < 140);
+        assert!(events().contains(&RawEvent::FairLaunchDrawn(0, draw)));
+
+        assert_eq!(Erc20::claim_sale(Origin::signed(bob()), 0), Ok(()));
+        assert_eq!(Erc20::claim_sale(Origin::signed(charlie()), 0), Ok(()));
+
+        // the cap is split between the orders, each pays only for what it got
+        let bob_filled = Erc20::balance_of(0, &bob()) - 100;
+        let charlie_filled = Erc20::balance_of(0, &charlie());
+        assert_eq!(bob_filled + charlie_filled, 100);
+        assert_eq!(Balances::free_balance(&bob()), 10_000 - 2 * bob_filled);
+        assert_eq!(Balances::free_balance(&charlie()), 1000 - 2 * charlie_filled);
+        assert_eq!(Balances::free_balance(&alice()), 9_000 + 200);
+        assert!(Erc20::sale(0).is_none());
+        assert!(Erc20::fair_launch(0).is_none());
+    });
+}
+
+#[test]
+fn fair_launch_within_the_cap_fills_every_order() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Erc20::start_fair_launch(Origin::signed(alice()), 0, 2, 100, 0, 0, 10), Ok(()));
+        assert_eq!(Erc20::purchase(Origin::signed(bob()), 0, 100), Ok(()));
+        // a fair launch is not finalised early when its orders reach the cap
+        assert_eq!(Erc20::finalise_sale(Origin::signed(bob()), 0), Err("Sale has not ended"));
+
+        System::set_block_number(11);
+        assert_eq!(Erc20::finalise_sale(Origin::signed(bob()), 0), Ok(()));
+        assert_eq!(Erc20::fair_launch(0).unwrap().draw, None);
+        assert_eq!(Erc20::claim_sale(Origin::signed(bob()), 0), Ok(()));
+        assert_eq!(Erc20::balance_of(0, &bob()), 200);
+        assert_eq!(Balances::free_balance(&alice()), 10_200);
+    });
+}
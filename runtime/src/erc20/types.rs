@@ -0,0 +1,4 @@
+// data types stored and used by the erc20 module
+// defined in the erc20-multi-primitives crate, so that off-chain clients can share them
+
+pub use erc20_primitives::*;
@@ -38,7 +38,7 @@ pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
 
-mod erc20;
+pub mod erc20;
 
 /// Alias to Ed25519 pubkey that identifies an account on the chain.
 pub type AccountId = primitives::H256;
@@ -176,6 +176,13 @@ impl sudo::Trait for Runtime {
 impl erc20::Trait for Runtime {
 	type Event = Event;
 	type TokenBalance = u128;
+	type TransferHandler = ();
+	type ForceOrigin = erc20::EnsureRoot<AccountId>;
+	type ModerationOrigin = erc20::EnsureRoot<AccountId>;
+	type ComplianceCheck = ();
+	type Signature = Ed25519Signature;
+	type LogicModules = ();
+	type FeeDestination = ();
 }
 
 construct_runtime!(
@@ -191,7 +198,7 @@ construct_runtime!(
 		Indices: indices,
 		Balances: balances,
 		Sudo: sudo,
-		Erc20: erc20::{Module, Call, Storage, Event<T>},
+		Erc20: erc20::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -271,4 +278,42 @@ impl_runtime_apis! {
 			Aura::slot_duration()
 		}
 	}
+
+	impl erc20::api::Erc20Api<Block, AccountId, u128> for Runtime {
+		fn balance_of(token_id: u32, who: AccountId) -> u128 {
+			Erc20::balance_of(token_id, &who)
+		}
+
+		fn allowance(token_id: u32, owner: AccountId, spender: AccountId) -> u128 {
+			Erc20::allowance(token_id, &owner, &spender)
+		}
+
+		fn token_metadata(token_id: u32) -> Option<erc20::TokenInfo<u128>> {
+			Erc20::token_metadata(token_id)
+		}
+
+		fn token_count() -> u32 {
+			Erc20::token_id()
+		}
+
+		fn tokens() -> Vec<u32> {
+			Erc20::existing_tokens()
+		}
+
+		fn account_tokens(who: AccountId) -> Vec<u32> {
+			Erc20::account_tokens(who)
+		}
+
+		fn holders(token_id: u32, start: u32, limit: u32) -> Vec<(AccountId, u128)> {
+			Erc20::holders(token_id, start, limit)
+		}
+
+		fn incoming_allowances(spender: AccountId, start: u32, limit: u32) -> Vec<(u32, AccountId, u128)> {
+			Erc20::incoming_allowances_of(&spender, start, limit)
+		}
+
+		fn candles(pool_id: u32) -> Vec<erc20::Candle<u128>> {
+			Erc20::candles(pool_id)
+		}
+	}
 }
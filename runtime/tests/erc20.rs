@@ -0,0 +1,621 @@
+//! Integration tests of the erc20 module, running against the actual runtime.
+//!
+//! Calls are dispatched through the outer `Call` of the runtime and events are read back as
+//! outer `Event`s, so the wiring done by `construct_runtime!` is exercised as well.
+
+use node_template_runtime::{erc20, AccountId, Balances, BalancesConfig, Call, Erc20, Erc20Config, Event, GenesisConfig, Origin, Runtime, System};
+use node_template_runtime::BuildStorage;
+use node_template_runtime::erc20::{BondingCurve, LockableToken, LogicKind};
+use parity_codec::{Decode, Encode};
+use primitives::{Blake2Hasher, H256};
+use runtime_io::{with_externalities, TestExternalities};
+use runtime_primitives::traits::Dispatchable;
+
+fn alice() -> AccountId {
+	H256::repeat_byte(1)
+}
+
+fn bob() -> AccountId {
+	H256::repeat_byte(2)
+}
+
+fn charlie() -> AccountId {
+	H256::repeat_byte(3)
+}
+
+fn erc20_config() -> Erc20Config {
+	Erc20Config {
+		max_batch_size: 16,
+		max_name_length: 64,
+		max_ticker_length: 32,
+		max_uri_length: 256,
+		max_memo_length: 128,
+		max_guardians: 16,
+		max_intents: 256,
+		max_sender_intents: 16,
+		max_external_id_length: 32,
+		max_page_size: 1000,
+		max_account_tokens: 4,
+		reserved_ticker_prefixes: vec![b"LP-".to_vec()],
+		logic_timelock: 10,
+		lockdown_delay: 10,
+		creation_base_fee: 0,
+		creation_target: 4,
+		issuer_deposit: 0,
+		moderation_bond: 0,
+		swap_fee: 3000,
+		buyout_execution_period: 10,
+		candle_period: 10,
+		max_candles: 4,
+		tokens: vec![(b"Genesis".to_vec(), b"GEN".to_vec(), 1000, alice(), vec![(bob(), 100)])],
+		synthetic_tokens: 0,
+		synthetic_balances: 0,
+		ethereum_snapshots: vec![],
+	}
+}
+
+// alice and bob hold native balances, charlie holds none
+fn new_test_ext_with(erc20: Erc20Config) -> TestExternalities<Blake2Hasher> {
+	let storage = GenesisConfig {
+		consensus: None,
+		system: None,
+		timestamp: None,
+		indices: None,
+		balances: Some(BalancesConfig {
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			balances: vec![(alice(), 10_000), (bob(), 10_000)],
+			vesting: vec![],
+		}),
+		sudo: None,
+		erc20: Some(erc20),
+	}.build_storage().unwrap().0;
+	TestExternalities::new(storage)
+}
+
+fn new_test_ext() -> TestExternalities<Blake2Hasher> {
+	new_test_ext_with(erc20_config())
+}
+
+// dispatches a call of the erc20 module through the outer call of the runtime
+fn dispatch(origin: Origin, call: erc20::Call<Runtime>) -> Result<(), &'static str> {
+	Call::Erc20(call).dispatch(origin)
+}
+
+fn erc20_events() -> Vec<erc20::Event<node_template_runtime::Runtime>> {
+	System::events().into_iter().filter_map(|record| match record.event {
+		Event::erc20(event) => Some(event),
+		_ => None,
+	}).collect()
+}
+
+#[test]
+fn genesis_tokens_are_created() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(Erc20::token_id(), 1);
+		assert_eq!(Erc20::balance_of(0, &alice()), 900);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::holder_count(0), 2);
+		assert_eq!(Erc20::token_id_of(b"gen"), Some(0));
+	});
+}
+
+#[test]
+fn init_and_transfer_through_the_outer_call() {
+	with_externalities(&mut new_test_ext(), || {
+		let init = Call::Erc20(erc20::Call::init(b"Token".to_vec(), b"TKN".to_vec(), 500, None, false, false));
+		assert_eq!(init.dispatch(Origin::signed(bob())), Ok(()));
+
+		let transfer = Call::Erc20(erc20::Call::transfer(1, alice(), 200));
+		assert_eq!(transfer.dispatch(Origin::signed(bob())), Ok(()));
+
+		assert_eq!(Erc20::balance_of(1, &bob()), 300);
+		assert_eq!(Erc20::balance_of(1, &alice()), 200);
+		assert_eq!(Erc20::account_tokens(alice()), vec![0, 1]);
+		assert!(erc20_events().contains(&erc20::RawEvent::Transfer(1, bob(), alice(), 200, None)));
+	});
+}
+
+#[test]
+fn encoded_calls_dispatch_after_decoding() {
+	with_externalities(&mut new_test_ext(), || {
+		let encoded = Call::Erc20(erc20::Call::transfer(0, bob(), 50)).encode();
+		let call = Call::decode(&mut &encoded[..]).expect("call must decode");
+		assert_eq!(call.dispatch(Origin::signed(alice())), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 150);
+	});
+}
+
+#[test]
+fn failed_transfer_leaves_balances_untouched() {
+	with_externalities(&mut new_test_ext(), || {
+		let events = erc20_events().len();
+		let transfer = Call::Erc20(erc20::Call::transfer(0, alice(), 101));
+		assert_eq!(transfer.dispatch(Origin::signed(bob())), Err("Not enough balance."));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 900);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(erc20_events().len(), events);
+	});
+}
+
+#[test]
+fn root_only_calls_reject_signed_origins() {
+	with_externalities(&mut new_test_ext(), || {
+		let force = Call::Erc20(erc20::Call::force_transfer(0, bob(), alice(), 10));
+		assert!(force.dispatch(Origin::signed(alice())).is_err());
+	});
+}
+
+// fees
+
+#[test]
+fn init_burns_the_creation_fee_and_reserves_the_issuer_deposit() {
+	with_externalities(&mut new_test_ext_with(Erc20Config { creation_base_fee: 100, issuer_deposit: 50, ..erc20_config() }), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Token".to_vec(), b"TKN".to_vec(), 500, None, false, false)), Ok(()));
+
+		assert_eq!(Balances::free_balance(&alice()), 9_850);
+		assert_eq!(Balances::reserved_balance(&alice()), 50);
+		assert_eq!(Erc20::issuer_deposit_of(1), Some((alice(), 50)));
+		assert!(erc20_events().contains(&erc20::RawEvent::CreationFeePaid(alice(), 100)));
+	});
+}
+
+#[test]
+fn init_without_the_creation_fee_creates_nothing() {
+	with_externalities(&mut new_test_ext_with(Erc20Config { creation_base_fee: 100, ..erc20_config() }), || {
+		let init = erc20::Call::init(b"Token".to_vec(), b"TKN".to_vec(), 500, None, false, false);
+		assert_eq!(dispatch(Origin::signed(charlie()), init), Err("Not enough balance to pay the creation fee and issuer deposit"));
+
+		assert_eq!(Erc20::token_id(), 1);
+		assert_eq!(Erc20::token_id_of(b"tkn"), None);
+	});
+}
+
+#[test]
+fn fee_logic_must_be_provided_by_the_runtime() {
+	with_externalities(&mut new_test_ext(), || {
+		let schedule = erc20::Call::schedule_logic(0, LogicKind::Fee, Some(1));
+		assert_eq!(dispatch(Origin::signed(alice()), schedule), Err("Logic module does not exist"));
+
+		assert_eq!(Erc20::pending_logic((0, LogicKind::Fee)), None);
+	});
+}
+
+// burns
+
+#[test]
+fn transfers_burn_at_the_burn_rate() {
+	with_externalities(&mut new_test_ext(), || {
+		// a tenth of every transfer is burned
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::set_transfer_burn_rate(0, 100_000)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::transfer(0, bob(), 100)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 800);
+		assert_eq!(Erc20::balance_of(0, &bob()), 190);
+		assert_eq!(Erc20::token_details(0).total_supply, 990);
+		assert!(erc20_events().contains(&erc20::RawEvent::Burned(0, alice(), 10)));
+	});
+}
+
+#[test]
+fn burn_rate_is_set_by_the_owner_and_capped() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::set_transfer_burn_rate(0, 100_000)), Err("Only the token owner can do this"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::set_transfer_burn_rate(0, 1_000_001)), Err("Burn rate cannot exceed one million parts per million"));
+
+		assert_eq!(Erc20::transfer_burn_rate(0), 0);
+	});
+}
+
+#[test]
+fn burn_needs_a_burnable_token() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::burn(0, 10)), Err("Token is not burnable"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::set_burnable(0, true)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::burn(0, 101)), Err("Not enough balance."));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::burn(0, 10)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 90);
+		assert_eq!(Erc20::token_details(0).total_supply, 990);
+	});
+}
+
+// credit lines
+
+#[test]
+fn credit_line_covers_the_shortfall_and_is_repaid() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::grant_credit_line(0, bob(), 50)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, charlie(), 120)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 880);
+		assert_eq!(Erc20::balance_of(0, &bob()), 0);
+		assert_eq!(Erc20::balance_of(0, &charlie()), 120);
+		assert_eq!(Erc20::credit_line((0, bob())).map(|line| line.debt), Some(20));
+
+		// the next transfer to the borrower repays the debt
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::transfer(0, bob(), 30)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 870);
+		assert_eq!(Erc20::balance_of(0, &bob()), 10);
+		assert_eq!(Erc20::credit_line((0, bob())).map(|line| line.debt), Some(0));
+		assert!(erc20_events().contains(&erc20::RawEvent::CreditDrawn(0, bob(), alice(), 20)));
+		assert!(erc20_events().contains(&erc20::RawEvent::CreditRepaid(0, bob(), alice(), 20)));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::close_credit_line(0, bob())), Ok(()));
+		assert_eq!(Erc20::credit_line((0, bob())), None);
+	});
+}
+
+#[test]
+fn credit_line_draws_are_limited_and_checked() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::grant_credit_line(0, bob(), 50)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, charlie(), 200)), Err("Transfer exceeds the credit line"));
+
+		// the grantor leg is held to the transfer checks
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::freeze_account(0, alice())), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, charlie(), 120)), Err("Sender account is frozen"));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 900);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::balance_of(0, &charlie()), 0);
+		assert_eq!(Erc20::credit_line((0, bob())).map(|line| line.debt), Some(0));
+	});
+}
+
+// locks
+
+#[test]
+fn locked_balance_cannot_be_transferred() {
+	with_externalities(&mut new_test_ext(), || {
+		<Erc20 as LockableToken<_, _>>::set_lock(0, *b"testlock", &bob(), 60);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, alice(), 50)), Err("Balance is locked"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, alice(), 40)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &bob()), 60);
+
+		<Erc20 as LockableToken<_, _>>::remove_lock(0, *b"testlock", &bob());
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, alice(), 60)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &bob()), 0);
+	});
+}
+
+// swap programs
+
+#[test]
+fn swap_program_mints_the_new_token_until_the_deadline() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::init(b"New".to_vec(), b"NEW".to_vec(), 0, None, true, false)), Ok(()));
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::create_swap_program(0, 1, 2, 1, 10)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap(0, 50)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 50);
+		assert_eq!(Erc20::balance_of(1, &bob()), 100);
+		assert_eq!(Erc20::token_details(0).total_supply, 950);
+		assert!(erc20_events().contains(&erc20::RawEvent::Swapped(0, 1, bob(), 50, 100)));
+
+		// once the deadline has passed the old token is frozen
+		System::set_block_number(11);
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap(0, 50)), Err("Swap program has ended"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, alice(), 50)), Err("Token has been migrated"));
+	});
+}
+
+#[test]
+fn swap_program_failures_change_nothing() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::init(b"New".to_vec(), b"NEW".to_vec(), 0, None, false, false)), Ok(()));
+		assert!(dispatch(Origin::signed(alice()), erc20::Call::create_swap_program(0, 1, 2, 1, 10)).is_err());
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::create_swap_program(0, 1, 2, 1, 10)), Err("Token is not mintable"));
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::init(b"Newer".to_vec(), b"NWR".to_vec(), 0, None, true, false)), Ok(()));
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::create_swap_program(0, 2, 2, 1, 10)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap(0, 101)), Err("Not enough balance."));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::token_details(2).total_supply, 0);
+	});
+}
+
+// buyouts
+
+#[test]
+fn buyout_squeezes_out_the_holders_and_completes() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Cash".to_vec(), b"CSH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::propose_buyout(0, 1, 2, 1, 5)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::execute_buyout(0, vec![bob()])), Err("Buyout is not approved"));
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::approve_buyout(0)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::execute_buyout(0, vec![bob()])), Err("Dissent window has not ended"));
+
+		System::set_block_number(6);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::execute_buyout(0, vec![bob()])), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 1000);
+		assert_eq!(Erc20::balance_of(0, &bob()), 0);
+		assert_eq!(Erc20::balance_of(1, &alice()), 800);
+		assert_eq!(Erc20::balance_of(1, &bob()), 200);
+		assert_eq!(Erc20::buyout(0), None);
+		assert!(erc20_events().contains(&erc20::RawEvent::BuyoutCompleted(0)));
+	});
+}
+
+#[test]
+fn buyout_the_owner_cannot_pay_moves_nothing() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Cash".to_vec(), b"CSH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::propose_buyout(0, 1, 20, 1, 5)), Ok(()));
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::approve_buyout(0)), Ok(()));
+
+		System::set_block_number(6);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::execute_buyout(0, vec![bob()])), Err("Not enough balance."));
+
+		assert_eq!(Erc20::balance_of(0, &alice()), 900);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::balance_of(1, &alice()), 1000);
+	});
+}
+
+#[test]
+fn expired_buyout_can_be_closed_by_anyone() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Cash".to_vec(), b"CSH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::propose_buyout(0, 1, 2, 1, 5)), Ok(()));
+		assert_eq!(dispatch(Origin::ROOT, erc20::Call::approve_buyout(0)), Ok(()));
+
+		// the execution period ends 10 blocks after the dissent window
+		System::set_block_number(15);
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::close_buyout(0)), Err("Buyout has not expired"));
+
+		System::set_block_number(16);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::execute_buyout(0, vec![bob()])), Err("Buyout has expired"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::close_buyout(0)), Ok(()));
+
+		assert_eq!(Erc20::buyout(0), None);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert!(erc20_events().contains(&erc20::RawEvent::BuyoutExpired(0)));
+	});
+}
+
+// destroy
+
+#[test]
+fn destroyed_token_is_removed_in_steps() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::approve(0, charlie(), 10)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::start_destroy(0)), Err("Only the token owner can do this"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::start_destroy(0)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::transfer(0, alice(), 10)), Err("Token is being destroyed"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::finish_destroy(0)), Err("Token still has holders"));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::destroy_accounts(0, 10)), Ok(()));
+		assert_eq!(Erc20::holder_count(0), 0);
+		assert_eq!(Erc20::token_details(0).total_supply, 0);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::finish_destroy(0)), Err("Token still has approvals"));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::destroy_approvals(0, vec![bob()])), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::finish_destroy(0)), Ok(()));
+
+		assert_eq!(Erc20::owner_of(0), None);
+		assert_eq!(Erc20::existing_tokens(), Vec::<u32>::new());
+		assert_eq!(Erc20::token_id_of(b"gen"), None);
+		assert_eq!(Erc20::owned_tokens(alice()), Vec::<u32>::new());
+		assert!(erc20_events().contains(&erc20::RawEvent::TokenDestroyed(0)));
+	});
+}
+
+#[test]
+fn pooled_token_cannot_be_destroyed() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Other".to_vec(), b"OTH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::create_pool(0, 1)), Ok(()));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::start_destroy(0)), Err("Token is in a liquidity pool"));
+		assert!(!Erc20::is_destroying(0));
+	});
+}
+
+// hashed timelocks
+
+fn htlc_hash(preimage: &[u8; 32]) -> H256 {
+	H256::from(runtime_io::keccak_256(preimage))
+}
+
+#[test]
+fn htlc_is_claimed_with_the_preimage() {
+	with_externalities(&mut new_test_ext(), || {
+		System::set_block_number(1);
+		let preimage = [7u8; 32];
+		let hash = htlc_hash(&preimage);
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::lock_htlc(0, charlie(), 40, hash, 10)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::lock_htlc(0, charlie(), 10, hash, 10)), Err("Hash is already locked"));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 60);
+		assert_eq!(Erc20::reserved_balance_of((0, bob())), 40);
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::claim_htlc([8u8; 32])), Err("No hashed timelock for this preimage"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::claim_htlc(preimage)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &charlie()), 40);
+		assert_eq!(Erc20::reserved_balance_of((0, bob())), 0);
+		assert_eq!(Erc20::htlc(hash), None);
+		assert!(erc20_events().contains(&erc20::RawEvent::HtlcClaimed(hash, preimage, charlie(), 40)));
+	});
+}
+
+#[test]
+fn htlc_is_refunded_after_the_timeout() {
+	with_externalities(&mut new_test_ext(), || {
+		System::set_block_number(1);
+		let preimage = [7u8; 32];
+		let hash = htlc_hash(&preimage);
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::lock_htlc(0, charlie(), 40, hash, 1)), Err("Timeout must be in the future"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::lock_htlc(0, charlie(), 40, hash, 10)), Ok(()));
+
+		// a frozen receiver cannot claim, the tokens stay held
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::freeze_account(0, charlie())), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::claim_htlc(preimage)), Err("Receiver account is frozen"));
+		assert_eq!(Erc20::reserved_balance_of((0, bob())), 40);
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::refund_htlc(hash)), Err("Hashed timelock has not timed out yet"));
+
+		System::set_block_number(11);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::claim_htlc(preimage)), Err("Hashed timelock has timed out"));
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::refund_htlc(hash)), Ok(()));
+
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::reserved_balance_of((0, bob())), 0);
+		assert_eq!(Erc20::balance_of(0, &charlie()), 0);
+	});
+}
+
+// sales
+
+#[test]
+fn successful_sale_pays_the_issuer_and_delivers_the_tokens() {
+	with_externalities(&mut new_test_ext(), || {
+		System::set_block_number(1);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::start_sale(0, 2, 100, 50, 1, 10)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &alice()), 800);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::purchase(0, 60)), Ok(()));
+		assert_eq!(Balances::free_balance(&bob()), 9_880);
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::finalise_sale(0)), Err("Sale has not ended"));
+
+		System::set_block_number(11);
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::finalise_sale(0)), Ok(()));
+		assert_eq!(Balances::free_balance(&alice()), 10_120);
+		assert_eq!(Erc20::balance_of(0, &alice()), 840);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::claim_sale(0)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &bob()), 160);
+		assert_eq!(Erc20::reserved_balance_of((0, alice())), 0);
+		assert_eq!(Erc20::sale(0), None);
+		assert!(erc20_events().contains(&erc20::RawEvent::SaleFinalised(0, true, 60, 120)));
+	});
+}
+
+#[test]
+fn failed_sale_refunds_the_buyers() {
+	with_externalities(&mut new_test_ext(), || {
+		System::set_block_number(1);
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::start_sale(0, 2, 100, 50, 1, 10)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::purchase(0, 10)), Err("Issuer cannot buy in its own sale"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::purchase(0, 101)), Err("Purchase exceeds the sale cap"));
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::purchase(0, 10)), Err("Not enough balance to pay for the purchase"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::purchase(0, 10)), Ok(()));
+
+		System::set_block_number(11);
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::purchase(0, 10)), Err("Sale has ended"));
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::finalise_sale(0)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &alice()), 900);
+		assert_eq!(Balances::free_balance(&alice()), 10_000);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::claim_sale(0)), Ok(()));
+		assert_eq!(Balances::free_balance(&bob()), 10_000);
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert!(erc20_events().contains(&erc20::RawEvent::SaleRefunded(0, bob(), 20)));
+	});
+}
+
+// bonding curves
+
+#[test]
+fn bonding_curve_mints_on_buy_and_burns_on_sell() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Curve".to_vec(), b"CRV".to_vec(), 0, None, true, false)), Ok(()));
+		// a flat price of 2 native units per token
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::enable_bonding_curve(1, BondingCurve::Linear(2, 0, 1))), Ok(()));
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::buy(1, 10, 20)), Ok(()));
+		assert_eq!(Erc20::balance_of(1, &bob()), 10);
+		assert_eq!(Balances::free_balance(&bob()), 9_980);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::sell(1, 4, 8)), Ok(()));
+		assert_eq!(Erc20::balance_of(1, &bob()), 6);
+		assert_eq!(Balances::free_balance(&bob()), 9_988);
+		assert_eq!(Erc20::bonding_curve(1).map(|issuance| (issuance.supply, issuance.reserve)), Some((6, 12)));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::disable_bonding_curve(1)), Err("Tokens issued through the curve are outstanding"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::sell(1, 6, 12)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::disable_bonding_curve(1)), Ok(()));
+		assert_eq!(Balances::free_balance(&bob()), 10_000);
+		assert_eq!(Erc20::bonding_curve(1), None);
+	});
+}
+
+#[test]
+fn bonding_curve_trades_outside_the_limits_change_nothing() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Curve".to_vec(), b"CRV".to_vec(), 0, None, true, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::enable_bonding_curve(1, BondingCurve::Linear(2, 0, 1))), Ok(()));
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::buy(1, 10, 19)), Err("Cost exceeds the maximum"));
+		assert_eq!(dispatch(Origin::signed(charlie()), erc20::Call::buy(1, 10, 20)), Err("Not enough balance to pay for the tokens"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::buy(1, 10, 20)), Err("Custodian cannot trade on its own curve"));
+		assert_eq!(Erc20::token_details(1).total_supply, 0);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::buy(1, 10, 20)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::sell(1, 4, 9)), Err("Payout is below the minimum"));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::sell(1, 11, 0)), Err("Amount exceeds the curve supply"));
+
+		assert_eq!(Erc20::balance_of(1, &bob()), 10);
+		assert_eq!(Balances::free_balance(&bob()), 9_980);
+	});
+}
+
+// liquidity pools
+
+#[test]
+fn pool_adds_swaps_and_removes_liquidity() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Other".to_vec(), b"OTH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::create_pool(0, 1)), Ok(()));
+		let pool = Erc20::pool(0).expect("pool must exist");
+
+		// the first provider receives the square root of the product of the amounts
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::add_liquidity(0, 400, 100, 0)), Ok(()));
+		assert_eq!(Erc20::balance_of(pool.lp_token, &alice()), 200);
+		assert_eq!(Erc20::balance_of(0, &pool.account), 400);
+		assert_eq!(Erc20::balance_of(1, &pool.account), 100);
+
+		// 0.3% of the input stays in the pool as the swap fee
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap_exact_in(0, 0, 100, 0)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &bob()), 0);
+		assert_eq!(Erc20::balance_of(1, &bob()), 19);
+		assert!(erc20_events().contains(&erc20::RawEvent::PoolSwapped(0, bob(), 0, 100, 1, 19)));
+
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::remove_liquidity(0, 200, 0, 0)), Ok(()));
+		assert_eq!(Erc20::balance_of(0, &alice()), 1000);
+		assert_eq!(Erc20::balance_of(1, &alice()), 981);
+		assert_eq!(Erc20::token_details(pool.lp_token).total_supply, 0);
+	});
+}
+
+#[test]
+fn pool_failures_move_no_leg() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::init(b"Other".to_vec(), b"OTH".to_vec(), 1000, None, false, false)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::create_pool(0, 0)), Err("Pool tokens must differ"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::create_pool(0, 1)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::create_pool(1, 0)), Err("Tokens already have a pool"));
+		let pool = Erc20::pool(0).expect("pool must exist");
+
+		// the second leg is never written when the first one fails
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::add_liquidity(0, 2000, 100, 0)), Err("Not enough balance."));
+		assert_eq!(Erc20::balance_of(1, &alice()), 1000);
+		assert_eq!(Erc20::balance_of(1, &pool.account), 0);
+		assert_eq!(Erc20::token_details(pool.lp_token).total_supply, 0);
+
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap_exact_in(0, 0, 10, 0)), Err("Pool has no liquidity"));
+		assert_eq!(dispatch(Origin::signed(alice()), erc20::Call::add_liquidity(0, 400, 100, 0)), Ok(()));
+		assert_eq!(dispatch(Origin::signed(bob()), erc20::Call::swap_exact_in(0, 0, 100, 1000)), Err("Payout is below the minimum"));
+		assert_eq!(Erc20::balance_of(0, &bob()), 100);
+		assert_eq!(Erc20::balance_of(0, &pool.account), 400);
+	});
+}
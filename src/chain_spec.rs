@@ -1,7 +1,7 @@
 use primitives::{Ed25519AuthorityId, ed25519};
 use node_template_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig
+	SudoConfig, IndicesConfig, Erc20Config
 };
 use substrate_service;
 
@@ -101,5 +101,33 @@ fn testnet_genesis(initial_authorities: Vec<Ed25519AuthorityId>, endowed_account
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		erc20: Some(Erc20Config {
+			max_batch_size: 256,
+			max_name_length: 64,
+			max_ticker_length: 32,
+			max_uri_length: 256,
+			max_memo_length: 128,
+			max_guardians: 16,
+			max_intents: 256,
+			max_sender_intents: 16,
+			max_external_id_length: 32,
+			max_page_size: 1000,
+			max_account_tokens: 64,
+			reserved_ticker_prefixes: vec![b"LP-".to_vec(), b"W".to_vec(), b"BR-".to_vec()],
+			logic_timelock: 14400,
+			lockdown_delay: 14400,
+			creation_base_fee: 1000,
+			creation_target: 4,
+			issuer_deposit: 100,
+			moderation_bond: 100,
+			swap_fee: 3000,
+			buyout_execution_period: 14400,
+			candle_period: 600,
+			max_candles: 96,
+			tokens: vec![],
+			synthetic_tokens: 0,
+			synthetic_balances: 0,
+			ethereum_snapshots: vec![],
+		}),
 	}
 }